@@ -11,12 +11,13 @@ regex = "1"
 
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::process;
+use std::sync::LazyLock;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 
 const USER_AGENT: &str = "DiscogsFormatFilter/0.1";
@@ -44,55 +45,1002 @@ const API_BASE: &str = "https://api.discogs.com";
 ///
 ///   # Skip search by passing a Discogs artist ID directly
 ///   discogs-format-filter.rs --id 12345 --has vinyl
+///
+///   # Hide releases already in your collection or wantlist
+///   discogs-format-filter.rs "Artist Name" --only vinyl --exclude-owned --exclude-wanted
+///
+///   # Run the same query over a list of artists (names or IDs, one per line)
+///   discogs-format-filter.rs --artists-from favourites.txt --only vinyl --price-limit 30
+///
+///   # Main releases and remixes, but not production credits
+///   discogs-format-filter.rs "Artist Name" --role main,remix --only vinyl
+///
+///   # Include records released under aliases and by the artist's groups
+///   discogs-format-filter.rs "Artist Name" --only vinyl --include-aliases --include-groups
+///
+///   # Never prompt (for cron): take the unique exact match or fail
+///   discogs-format-filter.rs "Artist Name" --pick exact --only vinyl
+///
+///   # Vinyl LPs only, skipping reissues among standalone releases
+///   discogs-format-filter.rs "Artist Name" --only vinyl --desc lp --not-desc reissue
+///
+///   # Is there a Japanese vinyl pressing?
+///   discogs-format-filter.rs "Artist Name" --only vinyl --country japan
+///
+///   # Original label vinyl only, ignoring the budget-label reissues
+///   discogs-format-filter.rs "Artist Name" --only vinyl --label-match harvest --not-label "music for pleasure"
+///
+///   # The jazz side of an artist who also writes film scores
+///   discogs-format-filter.rs "Artist Name" --only vinyl --genre jazz --not-style score
+///
+///   # Vinyl-only, not counting bootleg or promo CDs against a master
+///   discogs-format-filter.rs "Artist Name" --only vinyl --official-only --no-promo
+///
+///   # Under-the-radar vinyl: wanted by many, owned by few
+///   discogs-format-filter.rs "Artist Name" --only vinyl --min-want 50 --min-scarcity 2 --sort scarcity
+///
+///   # Cheapest first, by decade
+///   discogs-format-filter.rs "Artist Name" --only vinyl --sort price --group-by decade
+///
+///   # Record-fair shopping list: most-wanted records for $200 in total
+///   discogs-format-filter.rs --artists-from favourites.txt --only vinyl --budget 200 --objective want
+///
+///   # 70s originals only, no live albums, without spending API calls on the rest
+///   discogs-format-filter.rs "Artist Name" --only vinyl --year-from 1970 --year-to 1979 --title-exclude live
+///
+///   # Show why each release was excluded
+///   discogs-format-filter.rs "Artist Name" --only vinyl --explain
+///
+///   # Which pressings of a master are vinyl-only?
+///   discogs-format-filter.rs master 12345 --only vinyl
+///
+///   # Spot-check 20 search pre-filter exclusions per artist
+///   discogs-format-filter.rs "Artist Name" --not cd --verify-search 20
+///
+///   # Why was this master left out?
+///   discogs-format-filter.rs why master 12345 --only vinyl --price-limit 20
+///
+///   # Is it getting cheaper? Price change since the last run
+///   discogs-format-filter.rs "Artist Name" --only vinyl --show-trend
+///
+///   # Every recorded price of one master
+///   discogs-format-filter.rs prices history 12345
+///
+///   # Ping a webhook when a wanted record drops 20% or below $25
+///   discogs-format-filter.rs "Artist Name" --only vinyl --alert-drop 20 --alert-below 25 --alert-to https://example.com/hook
+///
+///   # An artist whose name is also a subcommand
+///   discogs-format-filter.rs --only vinyl -- why
+///
+///   # Apply a named profile from the config file
+///   discogs-format-filter.rs "Artist Name" --profile vinyl-cheap
+///
+/// Config file (default ~/.config/discogs-format-filter/config.yaml):
+///   token_command: pass show discogs    # or token_env / token_file
+///   cache_dir: ~/.cache/discogs-format-filter
+///   profiles:
+///     vinyl-cheap:
+///       only: [vinyl]
+///       ignore: [cassette]
+///       price_limit: 40
+///       min_want: 20
+///       main_only: true
+///       currency: USD
+///       output: text
+///   formats:                            # extend the built-in format list
+///     - name: Flexi-disc
+///       aliases: [flexi]
+///       search: true                    # use in the --only search pre-filter
 #[derive(Parser)]
 #[command(name = "discogs-format-filter")]
 struct Cli {
-    /// Artist name to search for
+    /// Artist name to search for. An artist named like a subcommand
+    /// ("master", "why", "prices") goes after `--`, or use --id.
     artist: Option<String>,
 
     /// Discogs artist ID (bypasses name search)
     #[arg(long)]
     id: Option<u64>,
 
+    /// Read artist names or IDs, one per line, from a file ("-" for stdin)
+    /// and report on each. Ambiguous names are skipped, not prompted for.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["artist", "id"])]
+    artists_from: Option<String>,
+
+    /// How to resolve an artist name with several search matches:
+    /// prompt (default; only when stdin is a terminal), first, exact
+    /// (require a unique exact name match), fail, or a 1-based result number
+    #[arg(long, value_parser = parse_pick_policy, default_value = "prompt")]
+    pick: PickPolicy,
+
     /// Require this media format (repeatable, case-insensitive)
-    #[arg(long = "has")]
+    #[arg(long = "has", global = true)]
     has: Vec<String>,
 
     /// Exclude this media format (repeatable, case-insensitive)
-    #[arg(long = "not")]
+    #[arg(long = "not", global = true)]
     not: Vec<String>,
 
     /// Only these formats allowed (repeatable, case-insensitive).
     /// Release must have at least one, and no formats outside this set.
-    #[arg(long = "only")]
+    #[arg(long = "only", global = true)]
     only: Vec<String>,
 
     /// Ignore these formats for filtering and display (repeatable, case-insensitive).
     /// Useful with --only: e.g. --only vinyl --ignore cassette
-    #[arg(long = "ignore")]
+    #[arg(long = "ignore", global = true)]
     ignore: Vec<String>,
 
-    /// Maximum lowest price (USD). Excludes releases above this price
-    /// or with nothing for sale.
-    #[arg(long = "price-limit")]
+    /// Require this format description on standalone releases, e.g. LP,
+    /// 7", Reissue (repeatable, case-insensitive; abbreviations like RE work)
+    #[arg(long = "desc", global = true)]
+    desc: Vec<String>,
+
+    /// Exclude standalone releases with this format description
+    /// (repeatable, case-insensitive)
+    #[arg(long = "not-desc", global = true)]
+    not_desc: Vec<String>,
+
+    /// Only consider pressings from these countries, as Discogs names them
+    /// (repeatable or comma-separated, e.g. US,UK,Japan). For masters, the
+    /// format filters then apply to those versions only, and price checks
+    /// to the passing version that stands in for the master.
+    #[arg(long, value_delimiter = ',', global = true)]
+    country: Vec<String>,
+
+    /// Ignore pressings from these countries (repeatable or comma-separated)
+    #[arg(long, value_delimiter = ',', global = true)]
+    not_country: Vec<String>,
+
+    /// Only consider pressings on this label: a Discogs label ID, or part of
+    /// the label name (repeatable, case-insensitive)
+    #[arg(long, value_name = "NAME|ID", global = true)]
+    label_match: Vec<String>,
+
+    /// Ignore pressings on this label: ID or part of the name (repeatable)
+    #[arg(long, value_name = "NAME|ID", global = true)]
+    not_label: Vec<String>,
+
+    /// Only include releases in one of these genres, e.g. Jazz (repeatable,
+    /// case-insensitive)
+    #[arg(long, global = true)]
+    genre: Vec<String>,
+
+    /// Exclude releases in this genre (repeatable, case-insensitive)
+    #[arg(long, global = true)]
+    not_genre: Vec<String>,
+
+    /// Only include releases with one of these styles, e.g. "Hard Bop"
+    /// (repeatable, case-insensitive)
+    #[arg(long, global = true)]
+    style: Vec<String>,
+
+    /// Exclude releases with this style (repeatable, case-insensitive)
+    #[arg(long, global = true)]
+    not_style: Vec<String>,
+
+    /// Skip bootlegs: pressings described as "Unofficial Release"
+    #[arg(long, global = true)]
+    official_only: bool,
+
+    /// Skip promos: pressings described as "Promo" or "White Label"
+    #[arg(long, global = true)]
+    no_promo: bool,
+
+    /// Skip pressings described as "Test Pressing"
+    #[arg(long, global = true)]
+    no_test_pressing: bool,
+
+    /// What a master's versions skipped by --official-only/--no-promo/
+    /// --no-test-pressing still count for
+    #[arg(long, value_enum, global = true)]
+    flagged_versions: Option<FlaggedPolicy>,
+
+    /// Treat unknown format names in --has/--not/--only/--ignore (and
+    /// descriptions in --desc/--not-desc) as an error instead of a warning
+    #[arg(long, global = true)]
+    strict_formats: bool,
+
+    /// Maximum lowest price (in --currency, default USD). Excludes releases
+    /// above this price or with nothing for sale.
+    #[arg(long = "price-limit", global = true)]
     price_limit: Option<f64>,
 
+    /// Only include releases at least this many Discogs users have
+    #[arg(long, global = true)]
+    min_have: Option<u32>,
+
+    /// Only include releases at least this many Discogs users want
+    #[arg(long, global = true)]
+    min_want: Option<u32>,
+
+    /// Only include releases with at least this average rating (0-5)
+    #[arg(long, global = true)]
+    min_rating: Option<f64>,
+
+    /// Only include releases with at least this want/have ratio, e.g. 2 for
+    /// twice as many users wanting it as having it
+    #[arg(long, global = true)]
+    min_scarcity: Option<f64>,
+
+    /// Order results by (default year). Year, title and price sort
+    /// ascending, counts and ratings highest first, unless --sort-order says
+    /// otherwise; items without a value always go last.
+    #[arg(long, value_enum, global = true)]
+    sort: Option<SortKey>,
+
+    /// Direction for --sort
+    #[arg(long, value_enum, global = true)]
+    sort_order: Option<SortOrder>,
+
+    /// Print results under a heading per decade, role, format or label
+    /// (text output only)
+    #[arg(long, value_enum, global = true)]
+    group_by: Option<GroupBy>,
+
+    /// Total spend (in --currency): pick the hits that maximize --objective
+    /// at their lowest price without going over, and print them as a
+    /// shopping list
+    #[arg(long, global = true)]
+    budget: Option<f64>,
+
+    /// What --budget maximizes (default count)
+    #[arg(long, value_enum, global = true)]
+    objective: Option<Objective>,
+
+    /// Weight for --objective priority as ID=WEIGHT, where ID is a master or
+    /// release ID (repeatable or comma-separated; unlisted hits weigh 1)
+    #[arg(long, global = true, value_delimiter = ',', value_parser = parse_priority)]
+    priority: Vec<(u64, u64)>,
+
+    /// Currency for prices (e.g. USD, EUR, GBP, JPY)
+    #[arg(long, global = true)]
+    currency: Option<String>,
+
+    /// Output mode for results
+    #[arg(long, value_enum, global = true)]
+    output: Option<OutputMode>,
+
+    /// Config file path (default: ~/.config/discogs-format-filter/config.yaml)
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    /// Apply a named profile of filter settings from the config file
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
     /// Show detailed per-request API logging
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
 
     /// Maximum number of releases to process (0 = unlimited)
     #[arg(long, default_value_t = 0)]
     limit: usize,
 
+    /// Also search the artist's aliases (other names they release under)
+    #[arg(long)]
+    include_aliases: bool,
+
+    /// Also search groups the artist is a member of
+    #[arg(long)]
+    include_groups: bool,
+
+    /// Also search the members of a group
+    #[arg(long)]
+    include_members: bool,
+
+    /// Skip releases before this year (releases with no year are kept)
+    #[arg(long, value_name = "YEAR", global = true)]
+    year_from: Option<u32>,
+
+    /// Skip releases after this year (releases with no year are kept)
+    #[arg(long, value_name = "YEAR", global = true)]
+    year_to: Option<u32>,
+
+    /// Only include titles matching this regex (case-insensitive)
+    #[arg(long, value_name = "REGEX", global = true)]
+    title_match: Option<String>,
+
+    /// Skip titles matching this regex (case-insensitive)
+    #[arg(long, value_name = "REGEX", global = true)]
+    title_exclude: Option<String>,
+
+    /// Only check masters, skipping standalone releases
+    #[arg(long, global = true)]
+    masters_only: bool,
+
+    /// Only check standalone releases, skipping masters
+    #[arg(long, global = true)]
+    releases_only: bool,
+
     /// Only include releases where the artist has a "Main" credit.
     /// Excludes guest appearances, remixes, production credits, etc.
-    #[arg(long)]
+    /// Same as --role main, so the two can't be combined.
+    #[arg(long, global = true)]
     main_only: bool,
 
+    /// Only include releases where the artist has one of these roles
+    /// (repeatable or comma-separated): main, appearance, trackappearance,
+    /// remix, producer, unofficialrelease, or any other Discogs role name
+    #[arg(long, value_delimiter = ',', value_parser = parse_role, global = true)]
+    role: Vec<Role>,
+
+    /// Drop releases where the artist is credited only in these roles
+    /// (repeatable or comma-separated)
+    #[arg(
+        long = "not-role",
+        value_delimiter = ',',
+        value_parser = parse_role,
+        global = true
+    )]
+    not_role: Vec<Role>,
+
     /// Add matching releases to your Discogs wantlist with a tagged note
     #[arg(long)]
     add_to_wantlist: bool,
+
+    /// Add matching releases to this collection folder (name or ID) with a
+    /// tagged note in the collection "Notes" field
+    #[arg(long = "add-to-collection", value_name = "FOLDER")]
+    add_to_collection: Option<String>,
+
+    /// Exclude releases already in your collection. For masters, any
+    /// owned version excludes the whole master.
+    #[arg(long, global = true)]
+    exclude_owned: bool,
+
+    /// Exclude releases already in your wantlist. For masters, any
+    /// wanted version excludes the whole master.
+    #[arg(long, global = true)]
+    exclude_wanted: bool,
+
+    /// Report why each excluded release was dropped: the deciding
+    /// pipeline stage and the offending format or price
+    #[arg(long)]
+    explain: bool,
+
+    /// Show each hit's price change since it was last seen (prices are
+    /// recorded in <cache_dir>/prices.jsonl on every run that fetches them)
+    #[arg(long)]
+    show_trend: bool,
+
+    /// Alert when a hit's lowest price falls below this. An `alert_below`
+    /// in the hit's wantlist note tag takes precedence; --add-to-wantlist
+    /// saves this value there.
+    #[arg(long)]
+    alert_below: Option<f64>,
+
+    /// Alert when a hit's lowest price dropped by at least this many
+    /// percent since the last run (`alert_drop` in the note tag takes
+    /// precedence, and is saved there by --add-to-wantlist)
+    #[arg(long)]
+    alert_drop: Option<f64>,
+
+    /// Where alerts go: stdout (the default), a file to append JSON lines
+    /// to, or an http(s) webhook URL to POST each alert to as JSON
+    /// (repeatable). On its own, checks the wantlist note thresholds only.
+    #[arg(long, value_name = "TARGET")]
+    alert_to: Vec<String>,
+
+    /// Re-check masters excluded by the bulk search pre-filter against
+    /// their /versions and report false exclusions. Optional value: how
+    /// many to sample per artist (default: check all)
+    #[arg(long, value_name = "SAMPLE", num_args = 0..=1, default_missing_value = "0")]
+    verify_search: Option<usize>,
+
+    /// With --verify-search: stop trusting search for an artist when more
+    /// than this percentage of checked exclusions turn out wrong
+    #[arg(
+        long,
+        value_name = "PCT",
+        default_value_t = 10.0,
+        requires = "verify_search"
+    )]
+    search_trust_threshold: f64,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every version of one master (country, label, catno, year,
+    /// formats) marked pass/fail against the format filter
+    Master {
+        /// Discogs master ID
+        id: u64,
+    },
+    /// Run one master or release through the pipeline with the current
+    /// flags and print each stage's verdict
+    Why {
+        /// Whether ID is a master or a release
+        kind: ItemKind,
+        /// Discogs master or release ID
+        id: u64,
+        /// Artist whose credit the role filter and inline pre-filter check
+        /// (default: the item's first credited artist)
+        #[arg(long, value_name = "ID")]
+        artist_id: Option<u64>,
+    },
+    /// Query the prices recorded in <cache_dir>/prices.jsonl
+    Prices {
+        #[command(subcommand)]
+        action: PricesAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum PricesAction {
+    /// Every recorded price of one master or release, oldest first
+    History {
+        /// Discogs master or release ID
+        id: u64,
+        /// Only this kind, when a master and a release share the ID
+        #[arg(long)]
+        kind: Option<ItemKind>,
+    },
+}
+
+/// What kind of Discogs item an ID refers to.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+enum ItemKind {
+    Master,
+    Release,
+}
+
+/// Policy for resolving an artist name that matches several search results.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PickPolicy {
+    /// Auto-pick a unique exact match, otherwise ask (stdin must be a TTY)
+    Prompt,
+    /// Unique exact match if any, otherwise the first result
+    First,
+    /// Require a unique exact (case-insensitive) name match
+    Exact,
+    /// Error whenever there is more than one result
+    Fail,
+    /// Take the Nth result (1-based)
+    Index(usize),
+}
+
+/// Parse an --priority value: `ID=WEIGHT`.
+fn parse_priority(s: &str) -> Result<(u64, u64), String> {
+    let (id, weight) = s
+        .split_once('=')
+        .ok_or("expected ID=WEIGHT, e.g. 12345=3")?;
+    let id = id.trim().parse().map_err(|_| format!("bad ID \"{id}\""))?;
+    let weight = weight
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad weight \"{weight}\""))?;
+    Ok((id, weight))
+}
+
+fn parse_pick_policy(s: &str) -> Result<PickPolicy, String> {
+    match s.to_lowercase().as_str() {
+        "prompt" => Ok(PickPolicy::Prompt),
+        "first" => Ok(PickPolicy::First),
+        "exact" => Ok(PickPolicy::Exact),
+        "fail" => Ok(PickPolicy::Fail),
+        n => match n.parse::<usize>() {
+            Ok(i) if i >= 1 => Ok(PickPolicy::Index(i)),
+            _ => Err("expected prompt, first, exact, fail or a result number".into()),
+        },
+    }
+}
+
+/// An artist's credit on a release, from the artist-releases `role` field.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Role {
+    Main,
+    Appearance,
+    TrackAppearance,
+    Remix,
+    Producer,
+    UnofficialRelease,
+    Other(RoleName),
+}
+
+/// Any other Discogs role name, e.g. "Written-By": compared by its key
+/// (lowercase, separators stripped), shown as given.
+#[derive(Clone, Debug)]
+struct RoleName {
+    key: String,
+    name: String,
+}
+
+impl PartialEq for RoleName {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for RoleName {}
+
+impl PartialOrd for RoleName {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RoleName {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl Role {
+    /// Parse a role name, ignoring case, spaces, hyphens and underscores
+    /// ("Track Appearance", "track-appearance" and "TrackAppearance" agree).
+    fn parse(s: &str) -> Self {
+        let key: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_lowercase();
+        match key.as_str() {
+            "main" => Role::Main,
+            "appearance" => Role::Appearance,
+            "trackappearance" => Role::TrackAppearance,
+            "remix" => Role::Remix,
+            "producer" => Role::Producer,
+            "unofficialrelease" | "unofficial" => Role::UnofficialRelease,
+            _ => Role::Other(RoleName {
+                key,
+                name: s.trim().to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Role::Main => "Main",
+            Role::Appearance => "Appearance",
+            Role::TrackAppearance => "TrackAppearance",
+            Role::Remix => "Remix",
+            Role::Producer => "Producer",
+            Role::UnofficialRelease => "UnofficialRelease",
+            Role::Other(r) => &r.name,
+        };
+        f.write_str(name)
+    }
+}
+
+impl Serialize for Role {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Role {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|s| Role::parse(&s))
+    }
+}
+
+fn parse_role(s: &str) -> Result<Role, String> {
+    Ok(Role::parse(s))
+}
+
+/// How --official-only/--no-promo/--no-test-pressing treat the flagged
+/// versions of a master.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+enum FlaggedPolicy {
+    /// Leave them out entirely: a bootleg CD doesn't stop a master from
+    /// being vinyl-only
+    #[default]
+    Ignore,
+    /// Their formats still count against --not/--only, but they can't be
+    /// the pressing that makes a master match
+    Count,
+}
+
+/// Result ordering for --sort.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+enum SortKey {
+    #[default]
+    Year,
+    Title,
+    /// Lowest marketplace price
+    Price,
+    /// Number of copies for sale
+    ForSale,
+    Have,
+    Want,
+    Rating,
+    /// want/have ratio
+    Scarcity,
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortKey {
+    /// Whether sorting needs community stats for masters, which costs a
+    /// release-detail fetch each.
+    fn needs_community(self) -> bool {
+        matches!(
+            self,
+            SortKey::Have | SortKey::Want | SortKey::Rating | SortKey::Scarcity
+        )
+    }
+
+    fn needs_price(self) -> bool {
+        matches!(self, SortKey::Price | SortKey::ForSale)
+    }
+
+    fn default_order(self) -> SortOrder {
+        match self {
+            SortKey::Year | SortKey::Title | SortKey::Price => SortOrder::Asc,
+            _ => SortOrder::Desc,
+        }
+    }
+
+    /// Numeric sort value of a hit; `None` (no year, nothing for sale,
+    /// unrated, ...) sorts last. Not used for titles.
+    fn value(self, r: &Info) -> Option<f64> {
+        let for_sale = r.num_for_sale.filter(|&n| n > 0);
+        match self {
+            SortKey::Year => r.year.map(f64::from),
+            SortKey::Title => None,
+            SortKey::Price => r.price(),
+            SortKey::ForSale => for_sale.map(f64::from),
+            SortKey::Have => r.community.as_ref().map(|c| f64::from(c.have)),
+            SortKey::Want => r.community.as_ref().map(|c| f64::from(c.want)),
+            SortKey::Rating => r.community.as_ref().and_then(Community::rating),
+            SortKey::Scarcity => r.community.as_ref().map(Community::scarcity),
+        }
+    }
+
+    /// Compare two hits in the given direction, keeping missing values last.
+    fn compare(self, order: SortOrder, a: &Info, b: &Info) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        let ord = if self == SortKey::Title {
+            a.title.to_lowercase().cmp(&b.title.to_lowercase())
+        } else {
+            match (self.value(a), self.value(b)) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => return Ordering::Less,
+                (None, Some(_)) => return Ordering::Greater,
+                (None, None) => return Ordering::Equal,
+            }
+        };
+        match order {
+            SortOrder::Asc => ord,
+            SortOrder::Desc => ord.reverse(),
+        }
+    }
+}
+
+/// What --budget maximizes.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+enum Objective {
+    /// Number of records
+    #[default]
+    Count,
+    /// Total community want
+    Want,
+    /// Total --priority weight
+    Priority,
+}
+
+impl std::fmt::Display for Objective {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Objective::Count => "count",
+            Objective::Want => "want",
+            Objective::Priority => "priority",
+        })
+    }
+}
+
+/// Headings for --group-by.
+#[derive(ValueEnum, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum GroupBy {
+    Decade,
+    Role,
+    Format,
+    Label,
+}
+
+impl GroupBy {
+    /// Heading a hit is listed under. Hits with several roles or formats
+    /// get a combined heading rather than being listed twice.
+    fn heading(self, r: &Info, filters: &Filters) -> Option<String> {
+        match self {
+            GroupBy::Decade => r.year.map(|y| format!("{}s", y / 10 * 10)),
+            GroupBy::Role => {
+                let names: Vec<_> = r.roles.iter().map(Role::to_string).collect();
+                Some(names.join(", ")).filter(|s| !s.is_empty())
+            }
+            GroupBy::Format => {
+                let visible: Vec<_> = r
+                    .formats
+                    .iter()
+                    .filter(|f| !filters.ignore.contains(&f.to_lowercase()))
+                    .map(String::as_str)
+                    .collect();
+                Some(visible.join(", ")).filter(|s| !s.is_empty())
+            }
+            GroupBy::Label => r.label.clone(),
+        }
+    }
+
+    /// Heading for hits without a value, listed after all the others.
+    fn missing(self) -> &'static str {
+        match self {
+            GroupBy::Decade => "Unknown year",
+            GroupBy::Role => "No role",
+            GroupBy::Format => "Unknown format",
+            GroupBy::Label => "No label",
+        }
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum OutputMode {
+    /// Human-readable listing
+    Text,
+    /// JSON document on stdout
+    Json,
+}
+
+// ── config file ────────────────────────────────────────────────
+
+/// Top-level config file: default settings plus named profiles.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    /// Environment variable holding the token (default DISCOGS_TOKEN)
+    token_env: Option<String>,
+    /// Shell command whose stdout is the token (e.g. "pass show discogs")
+    token_command: Option<String>,
+    /// File containing the token
+    token_file: Option<String>,
+    /// Directory for local state (default ~/.cache/discogs-format-filter)
+    cache_dir: Option<String>,
+    /// Currency used when neither the CLI nor the profile sets one
+    currency: Option<String>,
+    profiles: BTreeMap<String, Profile>,
+    /// Extra formats or aliases on top of the built-in registry
+    formats: Vec<FormatDef>,
+}
+
+/// A named bundle of filter flags. List values are merged with any given
+/// on the command line; scalar values are overridden by the command line.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Profile {
+    has: Vec<String>,
+    not: Vec<String>,
+    only: Vec<String>,
+    ignore: Vec<String>,
+    desc: Vec<String>,
+    not_desc: Vec<String>,
+    country: Vec<String>,
+    not_country: Vec<String>,
+    label_match: Vec<String>,
+    not_label: Vec<String>,
+    genre: Vec<String>,
+    not_genre: Vec<String>,
+    style: Vec<String>,
+    not_style: Vec<String>,
+    official_only: bool,
+    no_promo: bool,
+    no_test_pressing: bool,
+    flagged_versions: Option<FlaggedPolicy>,
+    price_limit: Option<f64>,
+    min_have: Option<u32>,
+    min_want: Option<u32>,
+    min_rating: Option<f64>,
+    min_scarcity: Option<f64>,
+    sort: Option<SortKey>,
+    sort_order: Option<SortOrder>,
+    group_by: Option<GroupBy>,
+    budget: Option<f64>,
+    objective: Option<Objective>,
+    /// --priority weights by master or release ID
+    priority: BTreeMap<u64, u64>,
+    year_from: Option<u32>,
+    year_to: Option<u32>,
+    title_match: Option<String>,
+    title_exclude: Option<String>,
+    masters_only: bool,
+    releases_only: bool,
+    main_only: bool,
+    role: Vec<Role>,
+    not_role: Vec<Role>,
+    exclude_owned: bool,
+    exclude_wanted: bool,
+    show_trend: bool,
+    alert_below: Option<f64>,
+    alert_drop: Option<f64>,
+    alert_to: Vec<String>,
+    currency: Option<String>,
+    output: Option<OutputMode>,
+}
+
+impl Config {
+    /// Load the config from `path`, or from the default location if `None`.
+    /// A missing default file yields an empty config; a missing explicit
+    /// file is an error.
+    fn load(path: Option<&str>) -> Result<Self, String> {
+        let (path, explicit) = match path {
+            Some(p) => (expand_home(p), true),
+            None => (default_config_path(), false),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_yml::from_str(&text).map_err(|e| format!("{path}: {e}")),
+            Err(e) if !explicit && e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{path}: {e}")),
+        }
+    }
+
+    /// Resolve the API token: the token env var if set, then token_command,
+    /// then token_file.
+    fn token(&self) -> Result<String, String> {
+        let var = self.token_env.as_deref().unwrap_or("DISCOGS_TOKEN");
+        if let Some(t) = std::env::var(var).ok().filter(|s| !s.is_empty()) {
+            return Ok(t);
+        }
+        if let Some(cmd) = &self.token_command {
+            let out = process::Command::new("sh")
+                .args(["-c", cmd])
+                .output()
+                .map_err(|e| format!("token_command: {e}"))?;
+            if !out.status.success() {
+                return Err(format!("token_command exited with {}", out.status));
+            }
+            let t = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            if !t.is_empty() {
+                return Ok(t);
+            }
+        }
+        if let Some(file) = &self.token_file {
+            let path = expand_home(file);
+            let t =
+                std::fs::read_to_string(&path).map_err(|e| format!("token_file {path}: {e}"))?;
+            let t = t.trim().to_string();
+            if !t.is_empty() {
+                return Ok(t);
+            }
+        }
+        Err(format!(
+            "{var} not set.\n  \
+             Get a personal access token at https://www.discogs.com/settings/developers\n  \
+             (or set token_command / token_file in the config file)"
+        ))
+    }
+
+    fn cache_dir(&self) -> String {
+        match &self.cache_dir {
+            Some(d) => expand_home(d),
+            None => {
+                let base = std::env::var("XDG_CACHE_HOME")
+                    .ok()
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| expand_home("~/.cache"));
+                format!("{base}/discogs-format-filter")
+            }
+        }
+    }
+}
+
+impl Cli {
+    /// Subcommands report on one item, so an artist given alongside one
+    /// is a mistake (usually an artist named like the subcommand).
+    fn check_subcommand(&self) -> Result<(), String> {
+        let name = match &self.command {
+            None => return Ok(()),
+            Some(Command::Master { .. }) => "master",
+            Some(Command::Why { .. }) => "why",
+            Some(Command::Prices { .. }) => "prices",
+        };
+        let artist = (self.artist.as_ref().map(|a| format!("artist \"{a}\"")))
+            .or(self.id.map(|id| format!("--id {id}")))
+            .or(self.artists_from.as_ref().map(|_| "--artists-from".into()));
+        match artist {
+            Some(a) => Err(format!(
+                "{a} can't be combined with the `{name}` subcommand (for an artist named \"{name}\", put it after `--`)"
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether this run checks hits for price alerts.
+    fn alerts_on(&self) -> bool {
+        self.alert_below.is_some() || self.alert_drop.is_some() || !self.alert_to.is_empty()
+    }
+
+    /// Fold a profile's settings into the parsed command line.
+    fn apply_profile(&mut self, p: &Profile) {
+        self.has.extend(p.has.iter().cloned());
+        self.not.extend(p.not.iter().cloned());
+        self.only.extend(p.only.iter().cloned());
+        self.ignore.extend(p.ignore.iter().cloned());
+        self.desc.extend(p.desc.iter().cloned());
+        self.not_desc.extend(p.not_desc.iter().cloned());
+        self.country.extend(p.country.iter().cloned());
+        self.not_country.extend(p.not_country.iter().cloned());
+        self.label_match.extend(p.label_match.iter().cloned());
+        self.not_label.extend(p.not_label.iter().cloned());
+        self.genre.extend(p.genre.iter().cloned());
+        self.not_genre.extend(p.not_genre.iter().cloned());
+        self.style.extend(p.style.iter().cloned());
+        self.not_style.extend(p.not_style.iter().cloned());
+        self.official_only |= p.official_only;
+        self.no_promo |= p.no_promo;
+        self.no_test_pressing |= p.no_test_pressing;
+        self.flagged_versions = self.flagged_versions.or(p.flagged_versions);
+        self.price_limit = self.price_limit.or(p.price_limit);
+        self.min_have = self.min_have.or(p.min_have);
+        self.min_want = self.min_want.or(p.min_want);
+        self.min_rating = self.min_rating.or(p.min_rating);
+        self.min_scarcity = self.min_scarcity.or(p.min_scarcity);
+        self.sort = self.sort.or(p.sort);
+        self.sort_order = self.sort_order.or(p.sort_order);
+        self.group_by = self.group_by.or(p.group_by);
+        self.budget = self.budget.or(p.budget);
+        self.objective = self.objective.or(p.objective);
+        // Later entries win, so the command line overrides the profile
+        let given = std::mem::take(&mut self.priority);
+        self.priority = p
+            .priority
+            .iter()
+            .map(|(&id, &w)| (id, w))
+            .chain(given)
+            .collect();
+        self.year_from = self.year_from.or(p.year_from);
+        self.year_to = self.year_to.or(p.year_to);
+        if self.title_match.is_none() {
+            self.title_match = p.title_match.clone();
+        }
+        if self.title_exclude.is_none() {
+            self.title_exclude = p.title_exclude.clone();
+        }
+        self.masters_only |= p.masters_only;
+        self.releases_only |= p.releases_only;
+        self.main_only |= p.main_only;
+        self.role.extend(p.role.iter().cloned());
+        self.not_role.extend(p.not_role.iter().cloned());
+        self.exclude_owned |= p.exclude_owned;
+        self.exclude_wanted |= p.exclude_wanted;
+        self.show_trend |= p.show_trend;
+        self.alert_below = self.alert_below.or(p.alert_below);
+        self.alert_drop = self.alert_drop.or(p.alert_drop);
+        self.alert_to.extend(p.alert_to.iter().cloned());
+        if self.currency.is_none() {
+            self.currency = p.currency.clone();
+        }
+        self.output = self.output.or(p.output);
+    }
+}
+
+fn default_config_path() -> String {
+    let base = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| expand_home("~/.config"));
+    format!("{base}/discogs-format-filter/config.yaml")
+}
+
+/// Expand a leading `~/` to $HOME.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
 }
 
 // ── API response types ─────────────────────────────────────────
@@ -102,14 +1050,12 @@ struct Pagination {
     #[allow(dead_code)]
     page: u32,
     pages: u32,
-    #[allow(dead_code)]
     #[serde(default)]
     items: u32,
 }
 
 #[derive(Deserialize)]
 struct SearchResponse {
-    #[allow(dead_code)]
     pagination: Pagination,
     results: Vec<SearchHit>,
 }
@@ -138,6 +1084,23 @@ struct ArtistDetail {
     id: u64,
     name: String,
     uri: Option<String>,
+    #[serde(default)]
+    profile: String,
+    #[serde(default)]
+    realname: Option<String>,
+    #[serde(default)]
+    members: Vec<ArtistLink>,
+    #[serde(default)]
+    groups: Vec<ArtistLink>,
+    #[serde(default)]
+    aliases: Vec<ArtistLink>,
+}
+
+/// A related artist (member, group or alias) on an artist-detail payload.
+#[derive(Deserialize)]
+struct ArtistLink {
+    id: u64,
+    name: String,
 }
 
 #[derive(Deserialize)]
@@ -153,10 +1116,13 @@ struct ArtistRelease {
     kind: String,
     title: String,
     year: Option<u32>,
-    role: Option<String>,
+    role: Option<Role>,
     /// Inline format string from artist-releases endpoint (release-type only).
     /// e.g. "CD, Album" or "Vinyl, 12\", LP". Not present on master-type items.
     format: Option<String>,
+    /// Name of the artist whose release list this came from (set after fetch)
+    #[serde(skip)]
+    credited_as: String,
 }
 
 #[derive(Deserialize)]
@@ -165,13 +1131,39 @@ struct MasterVersionsPage {
     versions: Vec<MasterVersion>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 struct MasterVersion {
+    id: u64,
     major_formats: Option<Vec<String>>,
+    /// Format descriptions, e.g. "LP, Album, RE"
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    catno: Option<String>,
+    #[serde(default)]
+    country: Option<String>,
+    /// Release date: "1969", "1969-09-26" or "0" when unknown
+    #[serde(default)]
+    released: Option<String>,
 }
 
-#[derive(Deserialize, Clone)]
+impl MasterVersion {
+    /// Four-digit release year, or "" when unknown.
+    fn year(&self) -> String {
+        self.released
+            .as_deref()
+            .filter(|r| !r.is_empty() && *r != "0")
+            .map(|r| r.chars().take(4).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
 struct ArtistCredit {
+    #[serde(default, skip_serializing)]
+    id: u64,
     name: String,
     #[serde(default)]
     join: String,
@@ -181,25 +1173,111 @@ struct ArtistCredit {
 
 #[derive(Deserialize)]
 struct MasterDetail {
+    #[serde(default)]
+    title: String,
+    /// 0 when unknown
+    #[serde(default)]
+    year: Option<u32>,
     lowest_price: Option<f64>,
     num_for_sale: Option<u32>,
     main_release: Option<u64>,
     #[serde(default)]
     artists: Vec<ArtistCredit>,
+    #[serde(default)]
+    genres: Vec<String>,
+    #[serde(default)]
+    styles: Vec<String>,
+    /// Usually absent on masters; see `fetch_release_community`
+    community: Option<Community>,
 }
 
 #[derive(Deserialize)]
 struct ReleaseDetail {
+    #[serde(default)]
+    title: String,
+    /// 0 when unknown
+    #[serde(default)]
+    year: Option<u32>,
+    #[serde(default)]
+    country: Option<String>,
+    #[serde(default)]
+    labels: Vec<LabelEntry>,
+    #[serde(default)]
+    genres: Vec<String>,
+    #[serde(default)]
+    styles: Vec<String>,
     formats: Option<Vec<FormatEntry>>,
     lowest_price: Option<f64>,
     num_for_sale: Option<u32>,
     #[serde(default)]
     artists: Vec<ArtistCredit>,
+    community: Option<Community>,
+}
+
+#[derive(Deserialize)]
+struct LabelEntry {
+    #[serde(default)]
+    id: u64,
+    name: String,
+    #[serde(default)]
+    catno: String,
+}
+
+/// Response from /labels/{id}
+#[derive(Deserialize)]
+struct LabelDetail {
+    name: String,
 }
 
 #[derive(Deserialize)]
 struct FormatEntry {
     name: String,
+    #[serde(default)]
+    descriptions: Vec<String>,
+}
+
+/// Have/want counts and rating from the `community` block of release detail
+#[derive(Deserialize, Serialize, Clone)]
+struct Community {
+    #[serde(default)]
+    have: u32,
+    #[serde(default)]
+    want: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating: Option<CommunityRating>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct CommunityRating {
+    #[serde(default)]
+    count: u32,
+    #[serde(default)]
+    average: f64,
+}
+
+impl Community {
+    /// Want/have ratio; a release nobody has yet counts as had by one.
+    fn scarcity(&self) -> f64 {
+        self.want as f64 / self.have.max(1) as f64
+    }
+
+    /// Average rating, if anyone has rated it.
+    fn rating(&self) -> Option<f64> {
+        self.rating
+            .as_ref()
+            .filter(|r| r.count > 0)
+            .map(|r| r.average)
+    }
+}
+
+impl std::fmt::Display for Community {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "have {}, want {}", self.have, self.want)?;
+        if let Some(avg) = self.rating() {
+            write!(f, ", rated {avg:.2}")?;
+        }
+        Ok(())
+    }
 }
 
 /// Response from /oauth/identity
@@ -222,12 +1300,82 @@ struct WantlistItem {
     notes: Option<String>,
 }
 
+/// Paginated collection folder response
+#[derive(Deserialize)]
+struct CollectionPage {
+    pagination: Pagination,
+    releases: Vec<CollectionItem>,
+}
+
+#[derive(Deserialize)]
+struct CollectionItem {
+    id: u64,
+}
+
+/// Response from /users/{u}/collection/folders
+#[derive(Deserialize)]
+struct CollectionFolders {
+    folders: Vec<CollectionFolder>,
+}
+
+#[derive(Deserialize)]
+struct CollectionFolder {
+    id: u64,
+    name: String,
+}
+
+/// Response from /users/{u}/collection/fields
+#[derive(Deserialize)]
+struct CollectionFields {
+    fields: Vec<CollectionField>,
+}
+
+#[derive(Deserialize)]
+struct CollectionField {
+    id: u64,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Response from /users/{u}/collection/releases/{release_id}
+#[derive(Deserialize)]
+struct CollectionInstances {
+    releases: Vec<CollectionInstance>,
+}
+
+#[derive(Deserialize)]
+struct CollectionInstance {
+    instance_id: u64,
+    folder_id: u64,
+    #[serde(default)]
+    notes: Vec<CollectionNote>,
+}
+
+#[derive(Deserialize)]
+struct CollectionNote {
+    field_id: u64,
+    value: String,
+}
+
+/// Response from POST /users/{u}/collection/folders/{f}/releases/{r}
+#[derive(Deserialize)]
+struct CollectionAdded {
+    instance_id: u64,
+}
+
 /// YAML-serializable tag entry for wantlist notes
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 struct FilterTag {
     query: String,
     artist: String,
     date: String,
+    /// Per-item price alert thresholds (see --alert-below/--alert-drop);
+    /// may be set by hand in the note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alert_below: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alert_drop: Option<f64>,
 }
 
 // ── API stats tracking ─────────────────────────────────────────
@@ -245,6 +1393,11 @@ struct ApiStats {
     skipped_early_exit: u32,
     skipped_prefilter: u32,
     skipped_search: u32,
+    skipped_year: u32,
+    skipped_title: u32,
+    skipped_kind: u32,
+    search_verified: u32,
+    search_false: u32,
     requeued: u32,
     requeue_ok: u32,
     requeue_fail: u32,
@@ -268,6 +1421,22 @@ impl ApiStats {
         self.retries_429 += 1;
     }
 
+    /// Whether any pre-filter or early exit saved a fetch; these are worth
+    /// reporting even on a cold run without dedup or cache hits.
+    fn any_skipped(&self) -> bool {
+        [
+            self.skipped_price,
+            self.skipped_early_exit,
+            self.skipped_prefilter,
+            self.skipped_search,
+            self.skipped_year,
+            self.skipped_title,
+            self.skipped_kind,
+        ]
+        .iter()
+        .any(|&n| n > 0)
+    }
+
     fn print_summary(&self, dedup_saved: usize) {
         eprintln!();
         eprintln!("── API usage summary ──────────────────────────────");
@@ -285,7 +1454,7 @@ impl ApiStats {
                 *ms as f64 / 1000.0
             );
         }
-        if dedup_saved > 0 || self.cache_hits > 0 {
+        if dedup_saved > 0 || self.cache_hits > 0 || self.any_skipped() {
             eprintln!("  Optimizations:");
             if dedup_saved > 0 {
                 eprintln!(
@@ -305,6 +1474,24 @@ impl ApiStats {
                     self.skipped_price
                 );
             }
+            if self.skipped_year > 0 {
+                eprintln!(
+                    "    Year filter:         {} (outside --year-from/--year-to)",
+                    self.skipped_year
+                );
+            }
+            if self.skipped_title > 0 {
+                eprintln!(
+                    "    Title filter:        {} (--title-match/--title-exclude)",
+                    self.skipped_title
+                );
+            }
+            if self.skipped_kind > 0 {
+                eprintln!(
+                    "    Type filter:         {} (--masters-only/--releases-only)",
+                    self.skipped_kind
+                );
+            }
             if self.skipped_search > 0 {
                 eprintln!(
                     "    Search pre-filter:   {} (masters excluded via bulk search)",
@@ -324,6 +1511,12 @@ impl ApiStats {
                 );
             }
         }
+        if self.search_verified > 0 {
+            eprintln!(
+                "  Search verified:   {} ({} false exclusions)",
+                self.search_verified, self.search_false
+            );
+        }
         if self.requeued > 0 {
             eprintln!(
                 "  Requeued:          {} ({} recovered, {} failed)",
@@ -346,15 +1539,18 @@ impl ApiStats {
 
 struct Discogs {
     token: String,
+    /// Currency abbreviation passed as `curr_abbr` on price-bearing endpoints
+    currency: String,
     agent: ureq::Agent,
     verbose: bool,
     stats: RefCell<ApiStats>,
 }
 
 impl Discogs {
-    fn new(token: String, verbose: bool) -> Self {
+    fn new(token: String, currency: String, verbose: bool) -> Self {
         Self {
             token,
+            currency,
             agent: ureq::AgentBuilder::new()
                 .timeout(Duration::from_secs(30))
                 .build(),
@@ -460,7 +1656,7 @@ impl Discogs {
                         .record_rate_pause(pause_start.elapsed().as_millis());
                 }
                 Err(ureq::Error::Status(401, _)) => {
-                    return Err("401 Unauthorized. Check your DISCOGS_TOKEN.".into());
+                    return Err("401 Unauthorized. Check your Discogs token.".into());
                 }
                 Err(ureq::Error::Status(404, _)) => {
                     return Err("404 Not Found".into());
@@ -471,14 +1667,14 @@ impl Discogs {
     }
 
     /// Send a request with the given HTTP method, JSON body, auth, rate-limit,
-    /// and 429 retry handling.
+    /// and 429 retry handling. Returns the raw response body.
     fn request(
         &self,
         method: &str,
         label: &str,
         path: &str,
         body: &serde_json::Value,
-    ) -> Result<(), String> {
+    ) -> Result<String, String> {
         let url = if path.starts_with("http") {
             path.to_string()
         } else {
@@ -529,7 +1725,9 @@ impl Discogs {
                             .record_rate_pause(pause_start.elapsed().as_millis());
                     }
 
-                    return Ok(());
+                    return resp
+                        .into_string()
+                        .map_err(|e| format!("{method} response: {e}"));
                 }
                 Err(ureq::Error::Status(429, _)) => {
                     let elapsed_ms = start.elapsed().as_millis();
@@ -560,312 +1758,1762 @@ impl Discogs {
 #[derive(Clone)]
 struct FetchedInfo {
     formats: BTreeSet<String>,
+    /// Format descriptions; only fetched for standalone releases
+    descriptions: Option<BTreeSet<String>>,
+    /// Pressing countries: the release's own, or those of a master's
+    /// versions that passed the version-level filters
+    countries: BTreeSet<String>,
+    /// Label and catalog number of the release, or of a master's preferred
+    /// version (see `VersionScan::preferred`)
+    label: Option<String>,
+    catno: Option<String>,
+    /// From master or release detail
+    genres: Vec<String>,
+    styles: Vec<String>,
+    /// From release detail; for masters, from the release standing in for
+    /// it, and only fetched when --min-have etc. or --sort need it
+    community: Option<Community>,
     lowest_price: Option<f64>,
     num_for_sale: Option<u32>,
     artists: Vec<ArtistCredit>,
     /// For masters: main_release from master-detail; for releases: the release id itself
     release_id: Option<u64>,
+    /// For masters: IDs of all versions seen while checking formats
+    version_ids: Vec<u64>,
+    /// Set when a check inside the fetch already decided to drop the item
+    rejection: Option<(Stage, String)>,
 }
 
 // ── Collected info per logical release ─────────────────────────
 
+#[derive(Serialize)]
 struct Info {
+    /// "master" or "release"
+    #[serde(skip)]
+    kind: &'static str,
+    #[serde(skip)]
+    id: u64,
     title: String,
     year: Option<u32>,
-    role: String,
+    roles: BTreeSet<Role>,
+    /// Identities (artist, aliases, groups, members) this release was found under
+    credited: Vec<String>,
     formats: BTreeSet<String>,
+    /// Format descriptions (standalone releases only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    descriptions: Option<BTreeSet<String>>,
+    countries: BTreeSet<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    catno: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    genres: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    styles: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    community: Option<Community>,
     url: String,
+    /// Shown only when a flag is about prices; see `market`
     lowest_price: Option<f64>,
     num_for_sale: Option<u32>,
+    /// The price as fetched, shown or not: what the price history records
+    /// and alerts check
+    #[serde(skip)]
+    market: Market,
     artists: Vec<ArtistCredit>,
     /// Concrete release ID suitable for wantlist (main_release for masters)
     release_id: Option<u64>,
+    /// For masters: IDs of all versions (used for owned/wanted exclusion)
+    #[serde(skip)]
+    version_ids: Vec<u64>,
+    #[serde(skip)]
+    rejection: Option<(Stage, String)>,
+    /// Last recorded price before this run (--show-trend)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_seen: Option<PriceSnapshot>,
 }
 
-// ── Entry point ────────────────────────────────────────────────
-
-fn main() {
-    if let Err(e) = run() {
-        eprintln!("error: {e}");
-        process::exit(1);
+impl Info {
+    /// Lowest price, if anything is for sale.
+    fn price(&self) -> Option<f64> {
+        self.lowest_price
+            .filter(|_| self.num_for_sale.unwrap_or(0) > 0)
     }
 }
 
-fn run() -> Result<(), String> {
-    let cli = Cli::parse();
-
-    let token = std::env::var("DISCOGS_TOKEN")
-        .ok()
-        .filter(|s| !s.is_empty())
-        .ok_or(
-            "DISCOGS_TOKEN not set.\n  \
-             Get a personal access token at https://www.discogs.com/settings/developers",
-        )?;
-
-    let has: HashSet<String> = cli.has.iter().map(|s| s.to_lowercase()).collect();
-    let not: HashSet<String> = cli.not.iter().map(|s| s.to_lowercase()).collect();
-    let only: HashSet<String> = cli.only.iter().map(|s| s.to_lowercase()).collect();
-    let ignore: HashSet<String> = cli.ignore.iter().map(|s| s.to_lowercase()).collect();
-    let have_filters = !has.is_empty() || !not.is_empty() || !only.is_empty();
-    let price_limit = cli.price_limit;
+/// Marketplace price of an item, from master or release detail.
+#[derive(Clone, Copy, Default)]
+struct Market {
+    lowest_price: Option<f64>,
+    num_for_sale: Option<u32>,
+}
 
-    if has.is_empty() && not.is_empty() && only.is_empty() {
-        eprintln!("(no format filters; listing all releases with their formats)");
+impl Market {
+    /// Lowest price, if anything is for sale.
+    fn price(&self) -> Option<f64> {
+        self.lowest_price
+            .filter(|_| self.num_for_sale.unwrap_or(0) > 0)
     }
+}
 
-    let api = Discogs::new(token, cli.verbose);
-    let need_price = price_limit.is_some();
-    // Always fetch master-detail for format-passing masters (for artists + main_release)
-    let need_detail = true;
-
-    // ── resolve artist ──────────────────────────────────────────
-    let artist_id = match (cli.id, &cli.artist) {
-        (Some(id), _) => id,
-        (None, Some(name)) => pick_artist(&api, name)?,
-        _ => return Err("provide an artist name or --id <ID>".into()),
-    };
+/// An artist given on the command line or in an --artists-from list.
+enum ArtistRef {
+    Id(u64),
+    Name(String),
+}
 
-    // ── show artist info ────────────────────────────────────────
-    let artist_path = format!("/artists/{artist_id}");
-    let artist_detail: ArtistDetail = api.get("artist-detail", &artist_path, &[])?;
-    let artist_uri = artist_detail.uri.as_deref().unwrap_or("(no URL)");
-    eprintln!("Artist: {} (id {})", artist_detail.name, artist_detail.id);
-    eprintln!("  {artist_uri}");
+impl std::fmt::Display for ArtistRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArtistRef::Id(id) => write!(f, "id {id}"),
+            ArtistRef::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
 
-    // ── fetch artist's release list ─────────────────────────────
-    eprintln!("Fetching release list...");
-    let all = fetch_artist_releases(&api, artist_id)?;
+// ── format registry ────────────────────────────────────────────
+
+/// Built-in Discogs formats: the name as the API spells it, lowercase
+/// aliases accepted on the command line, and whether the format is common
+/// enough to bulk-search for in the --only pre-filter.
+const BUILTIN_FORMATS: &[(&str, &[&str], bool)] = &[
+    ("Vinyl", &["lp", "record", "vinyl record"], false),
+    ("CD", &["compact disc"], true),
+    ("CDr", &["cd-r"], false),
+    ("File", &["digital", "download", "mp3", "flac", "wav"], true),
+    ("Cassette", &["tape", "mc"], false),
+    ("DVD", &[], true),
+    ("DVDr", &["dvd-r"], false),
+    ("Blu-ray", &["bluray", "blu ray", "bd"], true),
+    ("Box Set", &["boxset", "box"], true),
+    ("Shellac", &[], true),
+    ("Flexi-disc", &["flexi", "flexidisc", "flexi disc"], false),
+    ("Lathe Cut", &["lathe"], false),
+    ("Acetate", &[], false),
+    ("SACD", &[], false),
+    ("Minidisc", &["md"], false),
+    ("8-Track Cartridge", &["8-track", "8 track"], false),
+    ("Reel-To-Reel", &["reel", "reel to reel"], false),
+    ("DAT", &[], false),
+    ("VHS", &[], false),
+    ("Laserdisc", &[], false),
+    ("Memory Stick", &["usb"], false),
+    ("All Media", &[], false),
+];
+
+/// A format known to the registry. Config `formats:` entries use the same
+/// shape; an entry whose name matches a built-in adds to it.
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+struct FormatDef {
+    /// Name as Discogs spells it, e.g. "Flexi-disc"
+    name: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    /// Bulk-search for this format in the --only pre-filter
+    #[serde(default)]
+    search: bool,
+}
 
-    // ── OPTIMIZATION 1: dedup by (kind, id) ─────────────────────
-    // The artist releases endpoint returns the same master/release
-    // multiple times under different roles. Dedup, keeping the first
-    // occurrence (preserving role info) and merging roles for display.
-    let (deduped, dedup_saved) = dedup_releases(&all);
+/// Every format name the tool knows: canonical API spelling plus aliases.
+struct FormatRegistry {
+    formats: Vec<FormatDef>,
+}
 
-    // ── --main-only: drop non-Main roles before any API calls ───
-    let deduped: Vec<DedupRelease> = if cli.main_only {
-        let before = deduped.len();
-        let kept: Vec<_> = deduped
-            .into_iter()
-            .filter(|r| r.role.as_deref().unwrap_or("Main") == "Main")
+impl FormatRegistry {
+    fn new(extra: &[FormatDef]) -> Self {
+        let mut formats: Vec<FormatDef> = BUILTIN_FORMATS
+            .iter()
+            .map(|(name, aliases, search)| FormatDef {
+                name: name.to_string(),
+                aliases: aliases.iter().map(|a| a.to_string()).collect(),
+                search: *search,
+            })
             .collect();
-        let dropped = before - kept.len();
-        if dropped > 0 {
-            eprintln!("--main-only: dropped {dropped} non-Main releases");
+        for def in extra {
+            let aliases = def.aliases.iter().map(|a| a.to_lowercase());
+            match formats
+                .iter_mut()
+                .find(|f| f.name.eq_ignore_ascii_case(&def.name))
+            {
+                Some(f) => {
+                    f.aliases.extend(aliases);
+                    f.search |= def.search;
+                }
+                None => formats.push(FormatDef {
+                    name: def.name.clone(),
+                    aliases: aliases.collect(),
+                    search: def.search,
+                }),
+            }
         }
-        kept
-    } else {
-        deduped
-    };
+        Self { formats }
+    }
 
-    let masters: Vec<_> = deduped.iter().filter(|r| r.kind == "master").collect();
-    let singles: Vec<_> = deduped.iter().filter(|r| r.kind == "release").collect();
-    let total = masters.len() + singles.len();
+    /// Canonical API name for a format name or alias, case-insensitively.
+    fn canonical(&self, name: &str) -> Option<&str> {
+        let lc = name.trim().to_lowercase();
+        self.formats
+            .iter()
+            .find(|f| f.name.to_lowercase() == lc || f.aliases.contains(&lc))
+            .map(|f| f.name.as_str())
+    }
 
-    eprintln!(
-        "{} masters + {} standalone = {} unique releases ({} duplicates removed)",
-        masters.len(),
-        singles.len(),
-        total,
-        dedup_saved,
-    );
+    /// Closest known name or alias to a misspelt format, if any is near
+    /// enough to be a plausible typo.
+    fn suggest(&self, name: &str) -> Option<&str> {
+        let lc = name.trim().to_lowercase();
+        let max = (lc.chars().count() / 3).max(1);
+        self.formats
+            .iter()
+            .flat_map(|f| {
+                let canonical = f.name.to_lowercase();
+                std::iter::once(canonical).chain(f.aliases.iter().cloned())
+            })
+            .map(|known| (edit_distance(&lc, &known), known))
+            .filter(|(d, _)| *d <= max)
+            .min_by_key(|(d, _)| *d)
+            .and_then(|(_, known)| self.canonical(&known))
+    }
 
-    if total == 0 {
-        println!("No releases found.");
-        api.print_stats(dedup_saved);
-        return Ok(());
+    /// Lowercased canonical name for filter comparison; unknown names are
+    /// just lowercased.
+    fn normalize(&self, name: &str) -> String {
+        self.canonical(name).unwrap_or(name.trim()).to_lowercase()
     }
 
-    // ── OPTIMIZATION 7: search-based bulk pre-filter for masters ──
-    // Use the search endpoint to find which masters have disqualifying
-    // formats, eliminating them without individual /versions calls.
-    // Search can only EXCLUDE (we trust "format exists" results), never
-    // include — masters not found in search still get individual checks.
-    let mut search_excluded: HashSet<u64> = HashSet::new();
-    if have_filters && !masters.is_empty() {
-        let known_ids: HashSet<u64> = masters.iter().map(|m| m.id).collect();
+    /// Spelling to send to the search API for a (lowercased) format name.
+    fn api_name(&self, name: &str) -> String {
+        self.canonical(name).unwrap_or(name).to_string()
+    }
 
-        // Formats whose presence would disqualify a master
-        let mut exclude_formats: Vec<String> = Vec::new();
-        for f in &not {
-            if !ignore.contains(f) {
-                exclude_formats.push(title_case(f));
+    /// Formats bulk-searched for by the --only pre-filter.
+    fn search_formats(&self) -> impl Iterator<Item = &str> {
+        self.formats
+            .iter()
+            .filter(|f| f.search)
+            .map(|f| f.name.as_str())
+    }
+
+    /// Parse an artist-releases format string into one entry per format.
+    ///
+    /// The string looks like "CD, Album", "Vinyl, 7\", 45 RPM, Single" or
+    /// "2×Vinyl, LP, Album, Limited Edition, Clear"; multi-format releases
+    /// join entries with " + " or just list the next format name. Only
+    /// canonical names start an entry, not aliases: some aliases ("LP") are
+    /// also descriptions.
+    fn parse_inline(&self, fmt_str: &str) -> Vec<InlineFormat> {
+        let mut entries: Vec<InlineFormat> = Vec::new();
+        for seg in fmt_str.split(',').flat_map(|s| s.split(" + ")) {
+            let seg = seg.trim();
+            if seg.is_empty() {
+                continue;
             }
-        }
-        if !only.is_empty() {
-            // For --only, any common format NOT in the allowed set disqualifies
-            for fmt in &["CD", "File", "DVD", "Blu-ray", "Box Set", "Shellac"] {
-                let lc = fmt.to_lowercase();
-                if !only.contains(&lc) && !ignore.contains(&lc) {
-                    exclude_formats.push(fmt.to_string());
-                }
+            if let Some((qty, name)) = self.inline_format_name(seg) {
+                entries.push(InlineFormat {
+                    qty,
+                    name: name.to_string(),
+                    ..Default::default()
+                });
+                continue;
             }
-        }
-        exclude_formats.sort();
-        exclude_formats.dedup();
-
-        if !exclude_formats.is_empty() {
-            eprintln!("Bulk pre-filtering masters via search...");
-            for fmt in &exclude_formats {
-                eprint!("\r  Searching for masters with {fmt}...\x1b[K");
-                match search_masters_with_format(&api, &artist_detail.name, fmt) {
-                    Ok(ids) => {
-                        let hits: HashSet<u64> = ids.intersection(&known_ids).cloned().collect();
-                        if cli.verbose {
-                            eprintln!(
-                                "\r    search {fmt}: {} results, {} matched known masters\x1b[K",
-                                ids.len(),
-                                hits.len()
-                            );
-                        }
-                        search_excluded.extend(hits);
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "\r  warning: search for {fmt} failed ({e}), will check individually\x1b[K"
-                        );
-                    }
-                }
+            if entries.is_empty() {
+                // Descriptions before any format name: keep them on an
+                // unnamed entry rather than dropping them
+                entries.push(InlineFormat {
+                    qty: 1,
+                    ..Default::default()
+                });
             }
-            if !search_excluded.is_empty() {
-                eprintln!(
-                    "\r  Search pre-excluded {}/{} masters\x1b[K",
-                    search_excluded.len(),
-                    masters.len()
-                );
+            let entry = entries.last_mut().expect("entry pushed above");
+            match canonical_description(seg) {
+                Some(d) => entry.descriptions.push(d.to_string()),
+                None => entry.free_text.push(seg.to_string()),
             }
         }
+        entries
     }
 
-    // ── OPTIMIZATION 2: cache by ID to avoid re-fetching ────────
-    let mut master_cache: HashMap<u64, FetchedInfo> = HashMap::new();
-    let mut release_cache: HashMap<u64, FetchedInfo> = HashMap::new();
+    /// Match a segment like "Vinyl", "2×CD" or "3xFile" against the
+    /// canonical format names, returning the quantity and name.
+    fn inline_format_name(&self, seg: &str) -> Option<(u32, &str)> {
+        let lookup = |name: &str| {
+            self.formats
+                .iter()
+                .find(|f| f.name.eq_ignore_ascii_case(name.trim()))
+                .map(|f| f.name.as_str())
+        };
+        if let Some(name) = lookup(seg) {
+            return Some((1, name));
+        }
+        // Quantity prefix; names that start with a digit ("8-Track
+        // Cartridge") were matched whole above
+        let digits_end = seg.find(|c: char| !c.is_ascii_digit())?;
+        let qty: u32 = seg[..digits_end].parse().ok()?;
+        let rest = seg[digits_end..].strip_prefix(['×', 'x', 'X'])?;
+        lookup(rest).map(|name| (qty, name))
+    }
+}
 
-    let item_limit = if cli.limit > 0 { cli.limit } else { usize::MAX };
-    let mut infos: Vec<Info> = Vec::with_capacity(total);
-    let mut retry_queue: Vec<&DedupRelease> = Vec::new();
-    let mut n = 0usize;
+// ── inline format strings ──────────────────────────────────────
+
+/// Discogs format descriptions: the full name and the abbreviations used
+/// in artist-release format strings.
+const DESCRIPTIONS: &[(&str, &[&str])] = &[
+    ("LP", &[]),
+    ("EP", &[]),
+    ("Album", &[]),
+    ("Single", &[]),
+    ("Mini-Album", &["Mini"]),
+    ("Maxi-Single", &["Maxi"]),
+    ("Compilation", &["Comp"]),
+    ("Sampler", &["Smplr"]),
+    ("Mixed", &[]),
+    ("7\"", &[]),
+    ("10\"", &[]),
+    ("12\"", &[]),
+    ("33 ⅓ RPM", &["33 1/3 RPM"]),
+    ("45 RPM", &[]),
+    ("78 RPM", &[]),
+    ("Reissue", &["RE"]),
+    ("Repress", &["RP"]),
+    ("Remastered", &["RM"]),
+    ("Limited Edition", &["Ltd"]),
+    ("Numbered", &["Num"]),
+    ("Deluxe Edition", &[]),
+    ("Special Edition", &[]),
+    ("Club Edition", &["Club"]),
+    ("Promo", &[]),
+    ("White Label", &["W/Lbl"]),
+    ("Test Pressing", &["TP"]),
+    ("Unofficial Release", &["Unofficial"]),
+    ("Picture Disc", &["Pic"]),
+    ("Single Sided", &["S/Sided"]),
+    ("Stereo", &[]),
+    ("Mono", &[]),
+    ("Quadraphonic", &["Quad"]),
+    ("Enhanced", &["Enh"]),
+    ("Copy Protected", &["Copy Prot."]),
+    ("Mispress", &[]),
+    ("Misprint", &[]),
+    ("NTSC", &[]),
+    ("PAL", &[]),
+    ("MP3", &[]),
+    ("FLAC", &[]),
+    ("WAV", &[]),
+    ("AAC", &[]),
+    ("ALAC", &[]),
+    ("AIFF", &[]),
+];
+
+/// Full description name for a description or its abbreviation.
+fn canonical_description(s: &str) -> Option<&'static str> {
+    let s = s.trim();
+    DESCRIPTIONS
+        .iter()
+        .find(|(name, abbrevs)| {
+            name.eq_ignore_ascii_case(s) || abbrevs.iter().any(|a| a.eq_ignore_ascii_case(s))
+        })
+        .map(|(name, _)| *name)
+}
 
-    for m in &masters {
-        if n >= item_limit {
-            break;
+/// Lowercased full description name for filter comparison; unknown
+/// descriptions are just lowercased.
+fn normalize_description(s: &str) -> String {
+    canonical_description(s).unwrap_or(s.trim()).to_lowercase()
+}
+
+/// One format from an inline format string: "2×Vinyl, LP, Album, Limited
+/// Edition, Clear" is 2 × Vinyl with descriptions LP, Album, Limited
+/// Edition and free text "Clear".
+#[derive(Debug, Default, PartialEq)]
+struct InlineFormat {
+    qty: u32,
+    /// Canonical format name; empty if the string didn't start with one
+    name: String,
+    /// Known descriptions, by full name ("RE" becomes "Reissue")
+    descriptions: Vec<String>,
+    /// Everything else: colours, edition notes, etc.
+    free_text: Vec<String>,
+}
+
+/// The parts of one pressing the version-level filters look at.
+struct Pressing<'a> {
+    country: Option<&'a str>,
+    /// (label ID when known, label name)
+    labels: Vec<(Option<u64>, &'a str)>,
+    /// Known format descriptions, by full name
+    descriptions: Vec<&'static str>,
+}
+
+impl<'a> Pressing<'a> {
+    fn of_version(v: &'a MasterVersion) -> Self {
+        Self {
+            country: v.country.as_deref().filter(|c| !c.is_empty()),
+            labels: v.label.as_deref().map(|l| (None, l)).into_iter().collect(),
+            descriptions: v
+                .format
+                .iter()
+                .flat_map(|f| f.split(','))
+                .filter_map(canonical_description)
+                .collect(),
         }
-        n += 1;
-        eprint!("\r  [{n}/{total}] {}\x1b[K", trunc(&m.title, 50));
+    }
 
-        // Search pre-filter: skip masters already identified as having
-        // a disqualifying format (no individual API call needed)
-        if search_excluded.contains(&m.id) {
-            api.stats.borrow_mut().skipped_search += 1;
-            continue;
+    fn of_release(r: &'a ReleaseDetail) -> Self {
+        Self {
+            country: r.country.as_deref().filter(|c| !c.is_empty()),
+            labels: r
+                .labels
+                .iter()
+                .map(|l| (Some(l.id).filter(|&id| id != 0), l.name.as_str()))
+                .collect(),
+            descriptions: r
+                .formats
+                .iter()
+                .flatten()
+                .flat_map(|f| &f.descriptions)
+                .filter_map(|d| canonical_description(d))
+                .collect(),
         }
+    }
+}
 
-        let fetched = if let Some(cached) = master_cache.get(&m.id) {
-            api.stats.borrow_mut().cache_hits += 1;
-            cached.clone()
-        } else {
-            match fetch_master_info(
-                &api,
-                m.id,
-                need_price,
-                need_detail,
-                price_limit,
-                &has,
-                &not,
-                &only,
-                &ignore,
-            ) {
-                Ok(f) => {
-                    master_cache.insert(m.id, f.clone());
-                    f
-                }
-                Err(e) if is_transient(&e) => {
-                    if cli.verbose {
-                        eprintln!(
-                            "\n    [RETRY] queuing master {} ({}) for retry: {e}",
-                            m.id, m.title
-                        );
-                    }
-                    api.stats.borrow_mut().requeued += 1;
-                    retry_queue.push(m);
-                    continue;
-                }
-                Err(e) => {
-                    eprintln!("\n  warning: skipping master {} ({}): {e}", m.id, m.title);
-                    continue;
-                }
-            }
-        };
+/// Descriptions that mark a pressing as a bootleg, promo or test pressing,
+/// and the flag that excludes each.
+const FLAGGED_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("Unofficial Release", "--official-only"),
+    ("Promo", "--no-promo"),
+    ("White Label", "--no-promo"),
+    ("Test Pressing", "--no-test-pressing"),
+];
+
+/// A --label-match/--not-label value: part of a label name, or a Discogs
+/// label ID. IDs match release labels directly and master versions, which
+/// only carry names, by the label's resolved name.
+struct LabelPattern {
+    id: Option<u64>,
+    /// Lowercased; for IDs, filled in by `Filters::resolve_labels`
+    name: String,
+}
 
-        infos.push(Info {
-            title: m.title.clone(),
-            year: m.year.filter(|&y| y != 0),
-            role: m.role.clone().unwrap_or_else(|| "Main".into()),
-            formats: fetched.formats,
-            url: format!("https://www.discogs.com/master/{}", m.id),
-            lowest_price: if need_price {
-                fetched.lowest_price
-            } else {
-                None
+impl LabelPattern {
+    fn parse(s: &str) -> Self {
+        match s.trim().parse() {
+            Ok(id) => Self {
+                id: Some(id),
+                name: String::new(),
             },
-            num_for_sale: if need_price {
-                fetched.num_for_sale
-            } else {
-                None
+            Err(_) => Self {
+                id: None,
+                name: s.trim().to_lowercase(),
             },
-            artists: fetched.artists,
-            release_id: fetched.release_id,
-        });
+        }
     }
 
-    for s in &singles {
-        if n >= item_limit {
-            break;
+    fn matches(&self, id: Option<u64>, name: &str) -> bool {
+        match (self.id, id) {
+            (Some(want), Some(got)) => want == got,
+            (Some(_), None) => !self.name.is_empty() && name.to_lowercase() == self.name,
+            (None, _) => name.to_lowercase().contains(&self.name),
         }
-        n += 1;
-        eprint!("\r  [{n}/{total}] {}\x1b[K", trunc(&s.title, 50));
+    }
+}
 
-        // OPTIMIZATION 6: Pre-filter standalone releases using the inline
-        // format string from the artist-releases endpoint (e.g. "CD, Album").
-        // This avoids a full /releases/{id} fetch for the vast majority of
-        // standalones that will fail the format filter.
-        if have_filters {
-            if let Some(ref fmt_str) = s.format {
-                let inline_formats = parse_inline_formats(fmt_str);
-                let lc: HashSet<String> = inline_formats
-                    .iter()
-                    .map(|f| f.to_lowercase())
-                    .filter(|f| !ignore.contains(f))
-                    .collect();
+impl std::fmt::Display for LabelPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "{id}"),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Lowercased country name for comparison, folding a few common
+/// spellings onto the names Discogs uses.
+fn normalize_country(s: &str) -> String {
+    let lc = s.trim().to_lowercase();
+    let folded = match lc.as_str() {
+        "usa" | "united states" | "america" => "us",
+        "united kingdom" | "gb" | "great britain" | "england" => "uk",
+        "jp" | "jpn" => "japan",
+        _ => return lc,
+    };
+    folded.to_string()
+}
+
+/// Edit distance between two strings, by characters: insertions,
+/// deletions, substitutions and adjacent transpositions ("vinly") each
+/// cost one.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j] = distance between a[..i] and b[..j]
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (d[i - 1][j - 1] + cost)
+                .min(d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(d[i - 2][j - 2] + 1);
+            }
+            d[i][j] = best;
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Format and price filters shared by every artist in a run.
+/// Format names are normalized through the registry and lowercased for
+/// case-insensitive comparison.
+struct Filters {
+    has: HashSet<String>,
+    not: HashSet<String>,
+    only: HashSet<String>,
+    ignore: HashSet<String>,
+    /// Format descriptions (full names, lowercased) for standalone releases
+    desc: HashSet<String>,
+    not_desc: HashSet<String>,
+    /// Pressing countries (lowercased) for the version-level filter
+    country: HashSet<String>,
+    not_country: HashSet<String>,
+    label_match: Vec<LabelPattern>,
+    not_label: Vec<LabelPattern>,
+    /// Genres and styles, lowercased
+    genre: HashSet<String>,
+    not_genre: HashSet<String>,
+    style: HashSet<String>,
+    not_style: HashSet<String>,
+    /// FLAGGED_DESCRIPTIONS entries switched on by --official-only etc.
+    flagged: Vec<(&'static str, &'static str)>,
+    flagged_policy: FlaggedPolicy,
+    price_limit: Option<f64>,
+    min_have: Option<u32>,
+    min_want: Option<u32>,
+    min_rating: Option<f64>,
+    min_scarcity: Option<f64>,
+    /// Checked against the artist-releases list, before any detail fetch
+    roles: RoleFilter,
+    list: ListFilters,
+    registry: FormatRegistry,
+}
+
+/// Year, title and type filters, applied right after dedup.
+struct RoleFilter {
+    want: BTreeSet<Role>,
+    not: BTreeSet<Role>,
+}
+
+impl RoleFilter {
+    fn from_cli(cli: &Cli) -> Result<Self, String> {
+        if cli.main_only && !cli.role.is_empty() {
+            return Err("--main-only and --role conflict (use --role main,...)".into());
+        }
+        let mut want: BTreeSet<Role> = cli.role.iter().cloned().collect();
+        if cli.main_only {
+            want.insert(Role::Main);
+        }
+        Ok(Self {
+            want,
+            not: cli.not_role.iter().cloned().collect(),
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.want.is_empty() && self.not.is_empty()
+    }
+
+    /// Why an item the artist is credited on in `roles` fails, if it does.
+    fn rejection(&self, roles: &BTreeSet<Role>) -> Option<String> {
+        let names = |roles: &[&Role]| {
+            let names: Vec<String> = roles.iter().map(|r| r.to_string()).collect();
+            names.join(", ")
+        };
+        let all: Vec<&Role> = roles.iter().collect();
+        let remaining: Vec<&Role> = roles.iter().filter(|r| !self.not.contains(r)).collect();
+        if !self.not.is_empty() && remaining.is_empty() {
+            return Some(format!("only credited as {} (--not-role)", names(&all)));
+        }
+        if !self.want.is_empty() && !remaining.iter().any(|r| self.want.contains(r)) {
+            let want: Vec<&Role> = self.want.iter().collect();
+            return Some(format!(
+                "credited as {} (--role {})",
+                names(&remaining),
+                names(&want)
+            ));
+        }
+        None
+    }
+}
+
+struct ListFilters {
+    year_from: Option<u32>,
+    year_to: Option<u32>,
+    title_match: Option<regex::Regex>,
+    title_exclude: Option<regex::Regex>,
+    /// "master" or "release" for --masters-only/--releases-only
+    kind: Option<&'static str>,
+}
+
+impl ListFilters {
+    fn from_cli(cli: &Cli) -> Result<Self, String> {
+        let pattern = |flag: &str, p: &Option<String>| {
+            p.as_deref()
+                .map(|p| {
+                    regex::RegexBuilder::new(p)
+                        .case_insensitive(true)
+                        .build()
+                        .map_err(|e| format!("{flag}: {e}"))
+                })
+                .transpose()
+        };
+        let kind = match (cli.masters_only, cli.releases_only) {
+            (true, true) => return Err("--masters-only and --releases-only conflict".into()),
+            (true, false) => Some("master"),
+            (false, true) => Some("release"),
+            (false, false) => None,
+        };
+        Ok(Self {
+            year_from: cli.year_from,
+            year_to: cli.year_to,
+            title_match: pattern("--title-match", &cli.title_match)?,
+            title_exclude: pattern("--title-exclude", &cli.title_exclude)?,
+            kind,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.year_from.is_none()
+            && self.year_to.is_none()
+            && self.title_match.is_none()
+            && self.title_exclude.is_none()
+            && self.kind.is_none()
+    }
+
+    /// Why a master or release ("master" or "release" as `kind`) fails
+    /// these filters, if it does.
+    fn rejection(
+        &self,
+        kind: &str,
+        title: &str,
+        year: Option<u32>,
+        stats: &mut ApiStats,
+    ) -> Option<String> {
+        if let Some(only) = self.kind
+            && kind != only
+        {
+            stats.skipped_kind += 1;
+            return Some(format!("{kind} (--{only}s-only)"));
+        }
+        if let Some(year) = year.filter(|&y| y != 0) {
+            if self.year_from.is_some_and(|from| year < from) {
+                stats.skipped_year += 1;
+                return Some(format!("{year} (--year-from)"));
+            }
+            if self.year_to.is_some_and(|to| year > to) {
+                stats.skipped_year += 1;
+                return Some(format!("{year} (--year-to)"));
+            }
+        }
+        if let Some(re) = &self.title_match
+            && !re.is_match(title)
+        {
+            stats.skipped_title += 1;
+            return Some("title (--title-match)".into());
+        }
+        if let Some(m) = self.title_exclude.as_ref().and_then(|re| re.find(title)) {
+            stats.skipped_title += 1;
+            return Some(format!(
+                "title matches \"{}\" (--title-exclude)",
+                m.as_str()
+            ));
+        }
+        None
+    }
+}
+
+impl Filters {
+    /// Build the filters, checking every format name against the
+    /// registry. Unknown names warn (or fail with --strict-formats) with a
+    /// "did you mean" suggestion.
+    fn from_cli(cli: &Cli, registry: FormatRegistry) -> Result<Self, String> {
+        if let Some(r) = cli.min_rating
+            && !(0.0..=5.0).contains(&r)
+        {
+            return Err(format!("--min-rating must be between 0 and 5, got {r}"));
+        }
+        let mut unknown = Vec::new();
+        let flags = [
+            ("--has", &cli.has),
+            ("--not", &cli.not),
+            ("--only", &cli.only),
+            ("--ignore", &cli.ignore),
+        ];
+        for (flag, names) in flags {
+            for name in names {
+                if registry.canonical(name).is_some() {
+                    continue;
+                }
+                let hint = match registry.suggest(name) {
+                    Some(s) => format!("; did you mean \"{s}\"?"),
+                    None => String::new(),
+                };
+                unknown.push(format!("unknown format \"{name}\" in {flag}{hint}"));
+            }
+        }
+        for (flag, names) in [("--desc", &cli.desc), ("--not-desc", &cli.not_desc)] {
+            for name in names {
+                if canonical_description(name).is_none() {
+                    unknown.push(format!("unknown description \"{name}\" in {flag}"));
+                }
+            }
+        }
+        if cli.strict_formats && !unknown.is_empty() {
+            return Err(unknown.join("\n"));
+        }
+        for msg in &unknown {
+            eprintln!("warning: {msg}");
+        }
+
+        let lower = |v: &[String]| v.iter().map(|s| registry.normalize(s)).collect();
+        let desc = |v: &[String]| v.iter().map(|s| normalize_description(s)).collect();
+        let lowercase = |v: &[String]| v.iter().map(|s| s.trim().to_lowercase()).collect();
+        Ok(Self {
+            has: lower(&cli.has),
+            not: lower(&cli.not),
+            only: lower(&cli.only),
+            ignore: lower(&cli.ignore),
+            desc: desc(&cli.desc),
+            not_desc: desc(&cli.not_desc),
+            country: cli.country.iter().map(|c| normalize_country(c)).collect(),
+            not_country: cli
+                .not_country
+                .iter()
+                .map(|c| normalize_country(c))
+                .collect(),
+            label_match: cli
+                .label_match
+                .iter()
+                .map(|l| LabelPattern::parse(l))
+                .collect(),
+            not_label: cli
+                .not_label
+                .iter()
+                .map(|l| LabelPattern::parse(l))
+                .collect(),
+            genre: lowercase(&cli.genre),
+            not_genre: lowercase(&cli.not_genre),
+            style: lowercase(&cli.style),
+            not_style: lowercase(&cli.not_style),
+            flagged: FLAGGED_DESCRIPTIONS
+                .iter()
+                .copied()
+                .filter(|&(_, flag)| match flag {
+                    "--official-only" => cli.official_only,
+                    "--no-promo" => cli.no_promo,
+                    _ => cli.no_test_pressing,
+                })
+                .collect(),
+            flagged_policy: cli.flagged_versions.unwrap_or_default(),
+            price_limit: cli.price_limit,
+            min_have: cli.min_have,
+            min_want: cli.min_want,
+            min_rating: cli.min_rating,
+            min_scarcity: cli.min_scarcity,
+            roles: RoleFilter::from_cli(cli)?,
+            list: ListFilters::from_cli(cli)?,
+            registry,
+        })
+    }
+
+    fn have_formats(&self) -> bool {
+        !self.has.is_empty() || !self.not.is_empty() || !self.only.is_empty()
+    }
+
+    /// Whether some versions of a master may be left out of the format
+    /// check. The search pre-filter can't see versions, so it's skipped.
+    fn have_version_filters(&self) -> bool {
+        !self.country.is_empty()
+            || !self.not_country.is_empty()
+            || !self.label_match.is_empty()
+            || !self.not_label.is_empty()
+            || !self.flagged.is_empty()
+    }
+
+    /// Whether a single pressing passes the version-level filters.
+    fn version_ok(&self, p: &Pressing) -> bool {
+        self.pressing_rejection(p).is_none()
+    }
+
+    /// Why a single pressing fails the version-level filters, if it does.
+    fn pressing_rejection(&self, p: &Pressing) -> Option<String> {
+        let country = p.country.unwrap_or("unknown");
+        let c = normalize_country(country);
+        if !self.country.is_empty() && !self.country.contains(&c) {
+            return Some(format!("pressed in {country} (--country)"));
+        }
+        if self.not_country.contains(&c) {
+            return Some(format!("pressed in {country} (--not-country)"));
+        }
+        let on_label =
+            |pat: &LabelPattern| p.labels.iter().any(|&(id, name)| pat.matches(id, name));
+        let labels = || {
+            let names: Vec<&str> = p.labels.iter().map(|&(_, name)| name).collect();
+            if names.is_empty() {
+                "no label".to_string()
+            } else {
+                format!("on {}", names.join(", "))
+            }
+        };
+        if !self.label_match.is_empty() && !self.label_match.iter().any(on_label) {
+            return Some(format!("{} (--label-match)", labels()));
+        }
+        if self.not_label.iter().any(on_label) {
+            return Some(format!("{} (--not-label)", labels()));
+        }
+        self.flag_rejection(p.descriptions.iter().copied())
+    }
+
+    /// Why a pressing with these descriptions is excluded as a bootleg,
+    /// promo or test pressing, if it is.
+    fn flag_rejection<'a>(
+        &self,
+        descriptions: impl IntoIterator<Item = &'a str>,
+    ) -> Option<String> {
+        let descriptions: Vec<&str> = descriptions.into_iter().collect();
+        self.flagged
+            .iter()
+            .find(|(d, _)| descriptions.iter().any(|x| x.eq_ignore_ascii_case(d)))
+            .map(|(d, flag)| format!("{d} ({flag})"))
+    }
+
+    /// Look up the names of labels given by ID, so master versions (which
+    /// only carry label names) can be matched against them.
+    fn resolve_labels(&mut self, api: &Discogs) -> Result<(), String> {
+        for pat in self.label_match.iter_mut().chain(&mut self.not_label) {
+            let Some(id) = pat.id else { continue };
+            let path = format!("/labels/{id}");
+            let detail: LabelDetail = api
+                .get("label-detail", &path, &[])
+                .map_err(|e| format!("label {id}: {e}"))?;
+            eprintln!("Label {id}: {}", detail.name);
+            pat.name = detail.name.to_lowercase();
+        }
+        Ok(())
+    }
+
+    /// Check a release's genres and styles against --genre/--style and
+    /// their exclusions, naming the offending one on failure.
+    fn genre_rejection(&self, genres: &[String], styles: &[String]) -> Option<String> {
+        let checks = [
+            ("genre", genres, &self.genre, &self.not_genre),
+            ("style", styles, &self.style, &self.not_style),
+        ];
+        for (what, have, want, avoid) in checks {
+            let lc: Vec<String> = have.iter().map(|g| g.to_lowercase()).collect();
+            if let Some(bad) = have.iter().zip(&lc).find(|(_, l)| avoid.contains(*l)) {
+                return Some(format!("{} (--not-{what})", bad.0));
+            }
+            if !want.is_empty() && !lc.iter().any(|l| want.contains(l)) {
+                let listed = if have.is_empty() {
+                    format!("no {what}")
+                } else {
+                    have.join(", ")
+                };
+                return Some(format!("{listed} (--{what})"));
+            }
+        }
+        None
+    }
+
+    /// Check community stats against --min-have/--min-want/--min-rating/
+    /// --min-scarcity. Items without stats fail any of them.
+    fn community_rejection(&self, community: Option<&Community>) -> Option<String> {
+        if !self.have_community_filters() {
+            return None;
+        }
+        let Some(c) = community else {
+            return Some("no community stats".into());
+        };
+        if let Some(min) = self.min_have
+            && c.have < min
+        {
+            return Some(format!("have {} (--min-have {min})", c.have));
+        }
+        if let Some(min) = self.min_want
+            && c.want < min
+        {
+            return Some(format!("want {} (--min-want {min})", c.want));
+        }
+        if let Some(min) = self.min_rating {
+            match c.rating() {
+                Some(avg) if avg >= min => {}
+                Some(avg) => return Some(format!("rated {avg:.2} (--min-rating {min})")),
+                None => return Some(format!("unrated (--min-rating {min})")),
+            }
+        }
+        if let Some(min) = self.min_scarcity
+            && c.scarcity() < min
+        {
+            return Some(format!(
+                "want/have {:.2} (--min-scarcity {min})",
+                c.scarcity()
+            ));
+        }
+        None
+    }
+
+    /// Whether the inline pre-filter has anything to check.
+    fn have_inline_filters(&self) -> bool {
+        self.have_formats() || self.have_descriptions() || !self.flagged.is_empty()
+    }
+
+    /// Why an inline format string from the artist-releases list (e.g.
+    /// "CD, Album") fails the format, description or flag filters, if it
+    /// does.
+    fn inline_rejection(&self, fmt_str: &str) -> Option<String> {
+        let inline_formats = self.registry.parse_inline(fmt_str);
+        let lc: HashSet<String> = inline_formats
+            .iter()
+            .filter(|f| !f.name.is_empty())
+            .map(|f| f.name.to_lowercase())
+            .filter(|f| !self.ignore.contains(f))
+            .collect();
+
+        // Check --not: if excluded format found, skip
+        let not_fail = lc
+            .iter()
+            .find(|f| self.not.contains(*f))
+            .map(|f| format!("{f} (--not)"));
+        // Check --has: if required format missing, skip
+        let has_fail = || {
+            self.has
+                .iter()
+                .find(|h| !lc.contains(*h))
+                .map(|h| format!("missing {h} (--has)"))
+        };
+        // Check --only: if non-only format present, skip
+        let only_fail = || {
+            lc.iter()
+                .find(|f| !self.only.is_empty() && !self.only.contains(*f))
+                .map(|f| format!("{f} (outside --only)"))
+        };
+
+        // Check --desc/--not-desc against every entry's descriptions,
+        // then --official-only/--no-promo/--no-test-pressing
+        let desc_fail = || {
+            let descriptions = || inline_formats.iter().flat_map(|f| &f.descriptions);
+            self.description_rejection(descriptions())
+                .or_else(|| self.flag_rejection(descriptions().map(String::as_str)))
+        };
+
+        not_fail
+            .or_else(has_fail)
+            .or_else(only_fail)
+            .or_else(desc_fail)
+    }
+
+    fn have_community_filters(&self) -> bool {
+        self.min_have.is_some()
+            || self.min_want.is_some()
+            || self.min_rating.is_some()
+            || self.min_scarcity.is_some()
+    }
+
+    fn have_genres(&self) -> bool {
+        !self.genre.is_empty()
+            || !self.not_genre.is_empty()
+            || !self.style.is_empty()
+            || !self.not_style.is_empty()
+    }
+
+    fn have_descriptions(&self) -> bool {
+        !self.desc.is_empty() || !self.not_desc.is_empty()
+    }
+
+    /// Check a release's format descriptions against --desc/--not-desc,
+    /// naming the offending description on failure.
+    fn description_rejection<'a>(
+        &self,
+        descriptions: impl IntoIterator<Item = &'a String>,
+    ) -> Option<String> {
+        let lc: BTreeSet<String> = descriptions
+            .into_iter()
+            .map(|d| normalize_description(d))
+            .collect();
+        if let Some(d) = lc.iter().find(|d| self.not_desc.contains(*d)) {
+            return Some(format!("{d} (--not-desc)"));
+        }
+        let mut want: Vec<_> = self.desc.iter().collect();
+        want.sort();
+        want.into_iter()
+            .find(|d| !lc.contains(*d))
+            .map(|d| format!("missing {d} (--desc)"))
+    }
+
+    /// Check a set of format names against --has/--not/--only, after
+    /// dropping --ignore'd formats.
+    fn formats_ok<'a>(&self, formats: impl IntoIterator<Item = &'a String>) -> bool {
+        self.format_rejection(formats).is_none()
+    }
+
+    /// Like `formats_ok`, but names the offending format on failure,
+    /// e.g. "cd (--not)" or "missing vinyl (--has)".
+    fn format_rejection<'a>(
+        &self,
+        formats: impl IntoIterator<Item = &'a String>,
+    ) -> Option<String> {
+        let lc: BTreeSet<String> = formats
+            .into_iter()
+            .map(|f| f.to_lowercase())
+            .filter(|f| !self.ignore.contains(f))
+            .collect();
+        if let Some(f) = lc.iter().find(|f| self.not.contains(*f)) {
+            return Some(format!("{f} (--not)"));
+        }
+        let mut has: Vec<_> = self.has.iter().collect();
+        has.sort();
+        if let Some(h) = has.into_iter().find(|h| !lc.contains(*h)) {
+            return Some(format!("missing {h} (--has)"));
+        }
+        if !self.only.is_empty() {
+            if lc.is_empty() {
+                return Some("no formats (--only)".into());
+            }
+            if let Some(f) = lc.iter().find(|f| !self.only.contains(*f)) {
+                return Some(format!("{f} (outside --only)"));
+            }
+        }
+        None
+    }
+
+    /// Formats (spelled for the search API) whose presence on a master
+    /// disqualifies it, for the search pre-filter.
+    fn search_exclude_formats(&self) -> Vec<String> {
+        let mut exclude_formats: Vec<String> = Vec::new();
+        for f in &self.not {
+            if !self.ignore.contains(f) {
+                exclude_formats.push(self.registry.api_name(f));
+            }
+        }
+        if !self.only.is_empty() {
+            // For --only, any common format NOT in the allowed set disqualifies
+            for fmt in self.registry.search_formats() {
+                let lc = fmt.to_lowercase();
+                if !self.only.contains(&lc) && !self.ignore.contains(&lc) {
+                    exclude_formats.push(fmt.to_string());
+                }
+            }
+        }
+        exclude_formats.sort();
+        exclude_formats.dedup();
+        exclude_formats
+    }
+
+    /// Why a price fails --price-limit, if it does.
+    fn price_rejection(
+        &self,
+        num_for_sale: Option<u32>,
+        lowest_price: Option<f64>,
+        currency: &str,
+    ) -> Option<String> {
+        let limit = self.price_limit?;
+        match (num_for_sale, lowest_price) {
+            (Some(n), Some(p)) if n > 0 => (p > limit).then(|| {
+                format!(
+                    "lowest {} over limit {}",
+                    money(p, currency),
+                    money(limit, currency)
+                )
+            }),
+            _ => Some("nothing for sale".into()),
+        }
+    }
+}
+
+/// Pipeline stage that dropped an item, reported by --explain.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "kebab-case")]
+enum Stage {
+    /// --role/--not-role/--main-only against the artist's credit
+    RoleFilter,
+    /// --year-from/--year-to, --title-match/--title-exclude, --masters-only/--releases-only
+    ListFilter,
+    /// Bulk /database/search found a disqualifying format on the master
+    SearchPrefilter,
+    /// The inline format string on the artist-releases list failed
+    InlinePrefilter,
+    /// The pressing (or no version of a master) failed --country,
+    /// --label-match, --official-only etc.
+    VersionFilter,
+    /// Version paging stopped at the first disqualifying format
+    EarlyExit,
+    /// Full format check failed
+    FormatFilter,
+    /// --genre/--style or their exclusions failed (from master or release detail)
+    GenreFilter,
+    /// --min-have/--min-want/--min-rating/--min-scarcity failed
+    CommunityFilter,
+    /// Master price over the limit; detail fetch stopped there
+    PriceSkip,
+    /// Final price check failed
+    PriceFilter,
+    /// --exclude-owned
+    Owned,
+    /// --exclude-wanted
+    Wanted,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Stage::RoleFilter => "role-filter",
+            Stage::ListFilter => "list-filter",
+            Stage::SearchPrefilter => "search-prefilter",
+            Stage::InlinePrefilter => "inline-prefilter",
+            Stage::VersionFilter => "version-filter",
+            Stage::EarlyExit => "early-exit",
+            Stage::FormatFilter => "format-filter",
+            Stage::GenreFilter => "genre-filter",
+            Stage::CommunityFilter => "community-filter",
+            Stage::PriceSkip => "price-skip",
+            Stage::PriceFilter => "price-filter",
+            Stage::Owned => "owned",
+            Stage::Wanted => "wanted",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An item dropped by the pipeline, with the deciding stage and detail.
+#[derive(Serialize, Clone)]
+struct Rejection {
+    title: String,
+    year: Option<u32>,
+    url: String,
+    stage: Stage,
+    /// Offending format or price
+    detail: String,
+}
+
+impl Rejection {
+    fn new(r: &DedupRelease, stage: Stage, detail: String) -> Self {
+        Self {
+            title: r.title.clone(),
+            year: r.year.filter(|&y| y != 0),
+            url: format!("https://www.discogs.com/{}/{}", r.kind, r.id),
+            stage,
+            detail,
+        }
+    }
+
+    fn from_info(r: &Info, stage: Stage, detail: String) -> Self {
+        Self {
+            title: r.title.clone(),
+            year: r.year,
+            url: r.url.clone(),
+            stage,
+            detail,
+        }
+    }
+}
+
+/// One artist's report with its final hits and (for --explain) everything
+/// that was dropped along the way.
+type Selection<'a> = (&'a ArtistReport, Vec<&'a Info>, Vec<Rejection>);
+
+/// Everything fetched for one artist, before the final filter.
+struct ArtistReport {
+    artist: ArtistDetail,
+    infos: Vec<Info>,
+    /// Items dropped by pre-filters before any detail was fetched
+    rejected: Vec<Rejection>,
+    /// Unique releases after dedup and role filtering
+    total: usize,
+    dedup_saved: usize,
+}
+
+// ── Entry point ────────────────────────────────────────────────
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {e}");
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut cli = Cli::parse();
+    cli.check_subcommand()?;
+
+    let config = Config::load(cli.config.as_deref())?;
+    if let Some(name) = &cli.profile {
+        let profile = config.profiles.get(name).ok_or_else(|| {
+            let names: Vec<_> = config.profiles.keys().map(String::as_str).collect();
+            format!(
+                "unknown profile \"{name}\" (available: {})",
+                names.join(", ")
+            )
+        })?;
+        cli.apply_profile(profile);
+    }
+    let currency = cli
+        .currency
+        .clone()
+        .or_else(|| config.currency.clone())
+        .unwrap_or_else(|| "USD".into())
+        .to_uppercase();
+    let output = cli.output.unwrap_or(OutputMode::Text);
+
+    if let Some(Command::Prices { action }) = &cli.command {
+        let history = PriceHistory::load(&config.cache_dir());
+        match action {
+            &PricesAction::History { id, kind } => {
+                price_history_report(&history, id, kind, output)?
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(budget) = cli.budget
+        && budget <= 0.0
+    {
+        return Err(format!("--budget must be positive, got {budget}"));
+    }
+
+    let mut filters = Filters::from_cli(&cli, FormatRegistry::new(&config.formats))?;
+
+    if !filters.have_formats() {
+        eprintln!("(no format filters; listing all releases with their formats)");
+    }
+
+    let token = config.token()?;
+    let api = Discogs::new(token, currency.clone(), cli.verbose);
+    filters.resolve_labels(&api)?;
+
+    match cli.command {
+        Some(Command::Master { id }) => {
+            master_report(&api, &filters, id, output)?;
+            api.print_stats(0);
+            return Ok(());
+        }
+        Some(Command::Why {
+            kind,
+            id,
+            artist_id,
+        }) => {
+            why_report(&api, &cli, &filters, kind, id, artist_id, output)?;
+            api.print_stats(0);
+            return Ok(());
+        }
+        Some(Command::Prices { .. }) | None => {}
+    }
+
+    // ── resolve artist list ─────────────────────────────────────
+    let batch = cli.artists_from.is_some();
+    let targets: Vec<ArtistRef> = match (&cli.artists_from, cli.id, &cli.artist) {
+        (Some(src), _, _) => read_artist_list(src)?,
+        (None, Some(id), _) => vec![ArtistRef::Id(id)],
+        (None, None, Some(name)) => vec![ArtistRef::Name(name.clone())],
+        _ => return Err("provide an artist name, --id <ID> or --artists-from <FILE>".into()),
+    };
+
+    // ── load user collection / wantlist ─────────────────────────
+    let username = if cli.add_to_wantlist
+        || cli.add_to_collection.is_some()
+        || cli.exclude_owned
+        || cli.exclude_wanted
+        || cli.alerts_on()
+    {
+        let username = fetch_identity(&api)?;
+        if cli.verbose {
+            eprintln!("  Authenticated as: {username}");
+        }
+        Some(username)
+    } else {
+        None
+    };
+
+    let owned: HashSet<u64> = match (&username, cli.exclude_owned) {
+        (Some(username), true) => {
+            eprintln!("Fetching collection...");
+            let ids = fetch_collection_ids(&api, username)?;
+            if cli.verbose {
+                eprintln!("  Collection has {} releases", ids.len());
+            }
+            ids
+        }
+        _ => HashSet::new(),
+    };
+
+    let collection_target = match (&username, &cli.add_to_collection) {
+        (Some(username), Some(folder)) => {
+            let folder_id = resolve_collection_folder(&api, username, folder)?;
+            let field_id = resolve_notes_field(&api, username)?;
+            if cli.verbose {
+                eprintln!(
+                    "  Collection folder {folder:?} = id {folder_id}, notes field id {field_id}"
+                );
+            }
+            Some((folder_id, field_id))
+        }
+        _ => None,
+    };
+
+    let mut existing_notes: HashMap<u64, String> = match &username {
+        Some(username) if cli.add_to_wantlist || cli.exclude_wanted || cli.alerts_on() => {
+            eprintln!("Fetching wantlist...");
+            let notes = fetch_wantlist_notes(&api, username)?;
+            if cli.verbose {
+                eprintln!("  Wantlist has {} items", notes.len());
+            }
+            notes
+        }
+        _ => HashMap::new(),
+    };
+
+    let mut aliases = AliasMap::load(&config.cache_dir());
+
+    // ── run the pipeline for each artist ────────────────────────
+    let mut reports: Vec<ArtistReport> = Vec::new();
+    for (i, target) in targets.iter().enumerate() {
+        if batch {
+            eprintln!();
+            eprintln!("[{}/{}] {target}", i + 1, targets.len());
+        }
+        let result = match target {
+            &ArtistRef::Id(id) => Ok(id),
+            ArtistRef::Name(name) => pick_artist(&api, name, cli.pick, batch, &mut aliases),
+        }
+        .and_then(|id| process_artist(&api, &cli, &filters, id));
+        match result {
+            Ok(report) => reports.push(report),
+            Err(e) if batch => eprintln!("  warning: skipping artist {target}: {e}"),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // ── price history: compare with the last run, then record this one ──
+    let mut history = PriceHistory::load(&config.cache_dir());
+    if cli.show_trend {
+        for info in reports.iter_mut().flat_map(|r| &mut r.infos) {
+            info.last_seen = history.last_seen(info, &currency);
+        }
+    }
+
+    let selected: Vec<Selection> = reports
+        .iter()
+        .map(|r| {
+            let (hits, rejected) =
+                select_hits(r, &filters, &cli, &owned, &existing_notes, &currency);
+            (r, hits, rejected)
+        })
+        .collect();
+
+    let alerts = if cli.alerts_on() {
+        find_alerts(&selected, &history, &existing_notes, &cli, &currency)
+    } else {
+        Vec::new()
+    };
+    history.record(reports.iter().flat_map(|r| &r.infos), &currency);
+
+    let priorities: HashMap<u64, u64> = cli.priority.iter().copied().collect();
+    let shopping = cli.budget.map(|budget| {
+        let objective = cli.objective.unwrap_or_default();
+        plan_budget(&selected, budget, objective, &priorities)
+    });
+
+    // ── print results ───────────────────────────────────────────
+    let query_summary = build_query_summary(&filters, &currency);
+    if output == OutputMode::Json {
+        print_json_report(
+            &selected,
+            &query_summary,
+            &currency,
+            batch,
+            cli.explain,
+            shopping.as_ref(),
+            &alerts,
+        )?;
+    } else if !batch && selected.first().is_some_and(|(r, _, _)| r.total == 0) {
+        println!("No releases found.");
+    } else {
+        println!();
+        print_header(&filters, &currency);
+        for (r, hits, rejected) in &selected {
+            if batch {
+                println!("── {} (id {}) ──", r.artist.name, r.artist.id);
+                println!();
+            }
+            print_hits(hits, &r.artist.name, &filters, &currency, cli.group_by);
+            if cli.explain {
+                print_rejections(rejected);
+            }
+            if batch {
+                println!("  {} matching / {} total.", hits.len(), r.infos.len());
+                println!();
+            }
+        }
+        let matching: usize = selected.iter().map(|(_, h, _)| h.len()).sum();
+        let total: usize = selected.iter().map(|(r, _, _)| r.infos.len()).sum();
+        if batch {
+            println!(
+                "{matching} matching / {total} total across {} artists.",
+                selected.len()
+            );
+        } else {
+            println!("{matching} matching / {total} total.");
+        }
+        if let Some(list) = &shopping {
+            print_shopping_list(list, &currency);
+        }
+    }
+    if !alerts.is_empty() {
+        send_alerts(&alerts, &cli.alert_to, output);
+    }
+
+    // ── add to wantlist / collection folder ─────────────────────
+    if let Some(username) = &username {
+        for (r, hits, _) in &selected {
+            if hits.is_empty() {
+                continue;
+            }
+            let tag = FilterTag {
+                query: query_summary.clone(),
+                artist: r.artist.name.clone(),
+                date: today_str(),
+                alert_below: cli.alert_below,
+                alert_drop: cli.alert_drop,
+            };
+            if cli.add_to_wantlist {
+                add_hits_to_wantlist(&api, username, hits, &mut existing_notes, &tag);
+            }
+            if let Some(target) = collection_target {
+                add_hits_to_collection(&api, username, target, hits, &tag);
+            }
+        }
+    }
+
+    let dedup_saved: usize = reports.iter().map(|r| r.dedup_saved).sum();
+    api.print_stats(dedup_saved);
+
+    Ok(())
+}
+
+/// Run the fetch pipeline for one artist: release list, dedup, pre-filters,
+/// and per-master / per-release format and price lookups. The final filter
+/// is applied separately by `select_hits`.
+fn process_artist(
+    api: &Discogs,
+    cli: &Cli,
+    filters: &Filters,
+    artist_id: u64,
+) -> Result<ArtistReport, String> {
+    let have_filters = filters.have_formats();
+    // Prices are always fetched and recorded, but only shown with a flag
+    // that's about them
+    let show_price = filters.price_limit.is_some()
+        || cli.budget.is_some()
+        || cli.show_trend
+        || cli.sort.is_some_and(SortKey::needs_price);
+    // Always fetch master-detail for format-passing masters (for artists + main_release)
+    let need_detail = true;
+    let need_community = filters.have_community_filters()
+        || cli.sort.is_some_and(SortKey::needs_community)
+        || (cli.budget.is_some() && cli.objective == Some(Objective::Want));
+
+    // ── show artist info ────────────────────────────────────────
+    let artist_path = format!("/artists/{artist_id}");
+    let artist_detail: ArtistDetail = api.get("artist-detail", &artist_path, &[])?;
+    let artist_uri = artist_detail.uri.as_deref().unwrap_or("(no URL)");
+    eprintln!("Artist: {} (id {})", artist_detail.name, artist_detail.id);
+    eprintln!("  {artist_uri}");
+
+    // ── related identities: aliases, groups, members ────────────
+    let mut identities: Vec<(u64, String)> = vec![(artist_detail.id, artist_detail.name.clone())];
+    let related = [
+        (cli.include_aliases, "alias", &artist_detail.aliases),
+        (cli.include_groups, "group", &artist_detail.groups),
+        (cli.include_members, "member", &artist_detail.members),
+    ];
+    for (enabled, kind, links) in related {
+        if !enabled {
+            continue;
+        }
+        for link in links {
+            if !identities.iter().any(|(id, _)| *id == link.id) {
+                eprintln!("  + {kind}: {} (id {})", link.name, link.id);
+                identities.push((link.id, link.name.clone()));
+            }
+        }
+    }
+
+    // ── fetch artist's release list ─────────────────────────────
+    let mut all = Vec::new();
+    for (id, name) in &identities {
+        if identities.len() > 1 {
+            eprintln!("Fetching release list for {name}...");
+        } else {
+            eprintln!("Fetching release list...");
+        }
+        let mut releases = fetch_artist_releases(api, *id)?;
+        for r in &mut releases {
+            r.credited_as = name.clone();
+        }
+        all.extend(releases);
+    }
+
+    // ── OPTIMIZATION 1: dedup by (kind, id) ─────────────────────
+    // The artist releases endpoint returns the same master/release
+    // multiple times under different roles. Dedup, keeping the first
+    // occurrence (preserving role info) and merging roles for display.
+    let (deduped, dedup_saved) = dedup_releases(&all);
+
+    // ── --role / --not-role / --main-only: filter before any API calls ──
+    let mut rejected: Vec<Rejection> = Vec::new();
+    let deduped: Vec<DedupRelease> = deduped
+        .into_iter()
+        .filter(|r| match filters.roles.rejection(&r.roles) {
+            Some(detail) => {
+                rejected.push(Rejection::new(r, Stage::RoleFilter, detail));
+                false
+            }
+            None => true,
+        })
+        .collect();
+    if !rejected.is_empty() {
+        eprintln!("Role filter: dropped {} releases", rejected.len());
+    }
+
+    // ── year / title / type: also before any API calls ──────────
+    let role_rejected = rejected.len();
+    let deduped: Vec<DedupRelease> = deduped
+        .into_iter()
+        .filter(|r| {
+            let why =
+                filters
+                    .list
+                    .rejection(&r.kind, &r.title, r.year, &mut api.stats.borrow_mut());
+            match why {
+                Some(detail) => {
+                    rejected.push(Rejection::new(r, Stage::ListFilter, detail));
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect();
+    if rejected.len() > role_rejected {
+        eprintln!(
+            "Year/title/type filters: dropped {} releases",
+            rejected.len() - role_rejected
+        );
+    }
 
-                // Check --not: if excluded format found, skip
-                let not_fail = not.iter().any(|n| lc.contains(n));
-                // Check --has: if required format missing, skip
-                let has_fail = !has.is_empty() && !has.iter().all(|h| lc.contains(h));
-                // Check --only: if non-only format present, skip
-                let only_fail =
-                    !only.is_empty() && !lc.is_empty() && !lc.iter().all(|f| only.contains(f));
+    let masters: Vec<_> = deduped.iter().filter(|r| r.kind == "master").collect();
+    let singles: Vec<_> = deduped.iter().filter(|r| r.kind == "release").collect();
+    let total = masters.len() + singles.len();
+
+    eprintln!(
+        "{} masters + {} standalone = {} unique releases ({} duplicates removed)",
+        masters.len(),
+        singles.len(),
+        total,
+        dedup_saved,
+    );
+
+    if total == 0 {
+        return Ok(ArtistReport {
+            artist: artist_detail,
+            infos: Vec::new(),
+            rejected,
+            total,
+            dedup_saved,
+        });
+    }
+
+    // ── OPTIMIZATION 7: search-based bulk pre-filter for masters ──
+    // Use the search endpoint to find which masters have disqualifying
+    // formats, eliminating them without individual /versions calls.
+    // Search can only EXCLUDE (we trust "format exists" results), never
+    // include — masters not found in search still get individual checks.
+    let mut search_excluded: HashMap<u64, String> = HashMap::new();
+    if have_filters && !masters.is_empty() && !filters.have_version_filters() {
+        let known_ids: HashSet<u64> = masters.iter().map(|m| m.id).collect();
+
+        let exclude_formats = filters.search_exclude_formats();
+        if !exclude_formats.is_empty() {
+            eprintln!("Bulk pre-filtering masters via search...");
+            let searches = identities
+                .iter()
+                .flat_map(|(_, name)| exclude_formats.iter().map(move |fmt| (name, fmt)));
+            for (name, fmt) in searches {
+                eprint!("\r  Searching for {name} masters with {fmt}...\x1b[K");
+                match search_masters_with_format(api, name, fmt) {
+                    Ok(ids) => {
+                        let hits: HashSet<u64> = ids.intersection(&known_ids).cloned().collect();
+                        if cli.verbose {
+                            eprintln!(
+                                "\r    search {fmt}: {} results, {} matched known masters\x1b[K",
+                                ids.len(),
+                                hits.len()
+                            );
+                        }
+                        for id in hits {
+                            search_excluded.entry(id).or_insert_with(|| fmt.clone());
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "\r  warning: search for {fmt} failed ({e}), will check individually\x1b[K"
+                        );
+                    }
+                }
+            }
+            if !search_excluded.is_empty() {
+                eprintln!(
+                    "\r  Search pre-excluded {}/{} masters\x1b[K",
+                    search_excluded.len(),
+                    masters.len()
+                );
+            }
+        }
+    }
+
+    if let Some(sample) = cli.verify_search
+        && !search_excluded.is_empty()
+    {
+        verify_search_exclusions(
+            api,
+            &masters,
+            &mut search_excluded,
+            sample,
+            cli.search_trust_threshold,
+        );
+    }
+
+    // ── OPTIMIZATION 2: cache by ID to avoid re-fetching ────────
+    let mut master_cache: HashMap<u64, FetchedInfo> = HashMap::new();
+    let mut release_cache: HashMap<u64, FetchedInfo> = HashMap::new();
+
+    let item_limit = if cli.limit > 0 { cli.limit } else { usize::MAX };
+    let mut infos: Vec<Info> = Vec::with_capacity(total);
+    let mut retry_queue: Vec<&DedupRelease> = Vec::new();
+    let mut n = 0usize;
+
+    for m in &masters {
+        if n >= item_limit {
+            break;
+        }
+        n += 1;
+        eprint!("\r  [{n}/{total}] {}\x1b[K", trunc(&m.title, 50));
 
-                if not_fail || has_fail || only_fail {
-                    api.stats.borrow_mut().skipped_prefilter += 1;
+        // Search pre-filter: skip masters already identified as having
+        // a disqualifying format (no individual API call needed)
+        if let Some(fmt) = search_excluded.get(&m.id) {
+            api.stats.borrow_mut().skipped_search += 1;
+            rejected.push(Rejection::new(
+                m,
+                Stage::SearchPrefilter,
+                format!("{fmt} (search)"),
+            ));
+            continue;
+        }
+
+        let fetched = if let Some(cached) = master_cache.get(&m.id) {
+            api.stats.borrow_mut().cache_hits += 1;
+            cached.clone()
+        } else {
+            match fetch_master_info(api, m.id, filters, need_detail, need_community) {
+                Ok(f) => {
+                    master_cache.insert(m.id, f.clone());
+                    f
+                }
+                Err(e) if is_transient(&e) => {
+                    if cli.verbose {
+                        eprintln!(
+                            "\n    [RETRY] queuing master {} ({}) for retry: {e}",
+                            m.id, m.title
+                        );
+                    }
+                    api.stats.borrow_mut().requeued += 1;
+                    retry_queue.push(m);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("\n  warning: skipping master {} ({}): {e}", m.id, m.title);
                     continue;
                 }
             }
+        };
+
+        infos.push(Info {
+            kind: "master",
+            id: m.id,
+            title: m.title.clone(),
+            year: m.year.filter(|&y| y != 0),
+            roles: m.roles.clone(),
+            credited: m.credited.clone(),
+            formats: fetched.formats,
+            descriptions: fetched.descriptions,
+            countries: fetched.countries,
+            label: fetched.label,
+            catno: fetched.catno,
+            genres: fetched.genres,
+            styles: fetched.styles,
+            community: fetched.community,
+            url: format!("https://www.discogs.com/master/{}", m.id),
+            lowest_price: fetched.lowest_price.filter(|_| show_price),
+            num_for_sale: fetched.num_for_sale.filter(|_| show_price),
+            market: Market {
+                lowest_price: fetched.lowest_price,
+                num_for_sale: fetched.num_for_sale,
+            },
+            artists: fetched.artists,
+            release_id: fetched.release_id,
+            version_ids: fetched.version_ids,
+            rejection: fetched.rejection,
+            last_seen: None,
+        });
+    }
+
+    for s in &singles {
+        if n >= item_limit {
+            break;
+        }
+        n += 1;
+        eprint!("\r  [{n}/{total}] {}\x1b[K", trunc(&s.title, 50));
+
+        // OPTIMIZATION 6: Pre-filter standalone releases using the inline
+        // format string from the artist-releases endpoint (e.g. "CD, Album").
+        // This avoids a full /releases/{id} fetch for the vast majority of
+        // standalones that will fail the format filter.
+        if filters.have_inline_filters()
+            && let Some(reason) = s
+                .format
+                .as_deref()
+                .and_then(|f| filters.inline_rejection(f))
+        {
+            api.stats.borrow_mut().skipped_prefilter += 1;
+            rejected.push(Rejection::new(s, Stage::InlinePrefilter, reason));
+            continue;
         }
 
         let fetched = if let Some(cached) = release_cache.get(&s.id) {
             api.stats.borrow_mut().cache_hits += 1;
             cached.clone()
         } else {
-            match release_info(&api, s.id) {
-                Ok((formats, lowest_price, num_for_sale, artists)) => {
-                    let f = FetchedInfo {
-                        formats,
-                        lowest_price,
-                        num_for_sale,
-                        artists,
-                        release_id: Some(s.id),
-                    };
+            match release_info(api, s.id, filters) {
+                Ok(f) => {
                     release_cache.insert(s.id, f.clone());
                     f
                 }
@@ -887,300 +3535,1198 @@ fn run() -> Result<(), String> {
             }
         };
 
-        infos.push(Info {
-            title: s.title.clone(),
-            year: s.year.filter(|&y| y != 0),
-            role: s.role.clone().unwrap_or_else(|| "Main".into()),
-            formats: fetched.formats,
-            url: format!("https://www.discogs.com/release/{}", s.id),
-            lowest_price: if need_price {
-                fetched.lowest_price
-            } else {
-                None
-            },
-            num_for_sale: if need_price {
-                fetched.num_for_sale
-            } else {
-                None
-            },
-            artists: fetched.artists,
-            release_id: Some(s.id),
-        });
+        infos.push(Info {
+            kind: "release",
+            id: s.id,
+            title: s.title.clone(),
+            year: s.year.filter(|&y| y != 0),
+            roles: s.roles.clone(),
+            credited: s.credited.clone(),
+            formats: fetched.formats,
+            descriptions: fetched.descriptions,
+            countries: fetched.countries,
+            label: fetched.label,
+            catno: fetched.catno,
+            genres: fetched.genres,
+            styles: fetched.styles,
+            community: fetched.community,
+            url: format!("https://www.discogs.com/release/{}", s.id),
+            lowest_price: fetched.lowest_price.filter(|_| show_price),
+            num_for_sale: fetched.num_for_sale.filter(|_| show_price),
+            market: Market {
+                lowest_price: fetched.lowest_price,
+                num_for_sale: fetched.num_for_sale,
+            },
+            artists: fetched.artists,
+            release_id: Some(s.id),
+            version_ids: fetched.version_ids,
+            rejection: fetched.rejection,
+            last_seen: None,
+        });
+    }
+
+    // ── retry queue: process items that failed with transient errors ──
+    // Each item gets up to 5 total attempts (1 initial + 4 retries).
+    const MAX_ATTEMPTS: u32 = 5;
+    let mut pending = retry_queue;
+    let mut attempt = 2u32; // first retry is attempt 2
+
+    while !pending.is_empty() && attempt <= MAX_ATTEMPTS {
+        eprintln!(
+            "\r  Retrying {} item(s) (attempt {attempt}/{MAX_ATTEMPTS})...\x1b[K",
+            pending.len()
+        );
+        let mut still_failing: Vec<&DedupRelease> = Vec::new();
+
+        for item in &pending {
+            eprint!(
+                "\r  [retry {attempt}/{MAX_ATTEMPTS}] {}\x1b[K",
+                trunc(&item.title, 50)
+            );
+
+            let result = if item.kind == "master" {
+                fetch_master_info(api, item.id, filters, need_detail, need_community)
+            } else {
+                release_info(api, item.id, filters)
+            };
+
+            match result {
+                Ok(fetched) => {
+                    api.stats.borrow_mut().requeue_ok += 1;
+                    let kind = if item.kind == "master" {
+                        "master"
+                    } else {
+                        "release"
+                    };
+                    infos.push(Info {
+                        kind,
+                        id: item.id,
+                        title: item.title.clone(),
+                        year: item.year.filter(|&y| y != 0),
+                        roles: item.roles.clone(),
+                        credited: item.credited.clone(),
+                        formats: fetched.formats,
+                        descriptions: fetched.descriptions,
+                        countries: fetched.countries,
+                        label: fetched.label,
+                        catno: fetched.catno,
+                        genres: fetched.genres,
+                        styles: fetched.styles,
+                        community: fetched.community,
+                        url: format!("https://www.discogs.com/{kind}/{}", item.id),
+                        lowest_price: fetched.lowest_price.filter(|_| show_price),
+                        num_for_sale: fetched.num_for_sale.filter(|_| show_price),
+                        market: Market {
+                            lowest_price: fetched.lowest_price,
+                            num_for_sale: fetched.num_for_sale,
+                        },
+                        artists: fetched.artists,
+                        release_id: fetched.release_id,
+                        version_ids: fetched.version_ids,
+                        rejection: fetched.rejection,
+                        last_seen: None,
+                    });
+                }
+                Err(e) if is_transient(&e) && attempt < MAX_ATTEMPTS => {
+                    // Still transient and we have more attempts — keep in queue
+                    still_failing.push(item);
+                }
+                Err(e) => {
+                    api.stats.borrow_mut().requeue_fail += 1;
+                    eprintln!(
+                        "\n  warning: giving up on {} {} ({}) after {attempt} attempts: {e}",
+                        item.kind, item.id, item.title
+                    );
+                }
+            }
+        }
+
+        pending = still_failing;
+        attempt += 1;
+    }
+
+    eprintln!("\r  Done.\x1b[K");
+
+    Ok(ArtistReport {
+        artist: artist_detail,
+        infos,
+        rejected,
+        total,
+        dedup_saved,
+    })
+}
+
+/// `master <id>`: one row per version with its pass/fail verdict against
+/// the format filter.
+fn master_report(
+    api: &Discogs,
+    filters: &Filters,
+    master_id: u64,
+    output: OutputMode,
+) -> Result<(), String> {
+    let detail = fetch_master_detail(api, master_id)?;
+    eprintln!("Master: {} (id {master_id})", detail.title);
+
+    let mut versions = fetch_master_versions(api, master_id)?;
+    versions.sort_by_key(|v| (v.year(), v.country.clone().unwrap_or_default()));
+
+    let verdicts: Vec<bool> = versions
+        .iter()
+        .map(|v| {
+            filters.version_ok(&Pressing::of_version(v))
+                && filters.formats_ok(v.major_formats.iter().flatten())
+        })
+        .collect();
+    let passing = verdicts.iter().filter(|&&ok| ok).count();
+
+    if output == OutputMode::Json {
+        let rows: Vec<_> = versions
+            .iter()
+            .zip(&verdicts)
+            .map(|(v, ok)| {
+                let mut row = serde_json::to_value(v).unwrap_or_default();
+                row["pass"] = (*ok).into();
+                row
+            })
+            .collect();
+        let report = serde_json::json!({
+            "master": { "id": master_id, "title": detail.title },
+            "passing": passing,
+            "versions": rows,
+        });
+        let text =
+            serde_json::to_string_pretty(&report).map_err(|e| format!("JSON output: {e}"))?;
+        println!("{text}");
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 8]> = versions
+        .iter()
+        .zip(&verdicts)
+        .map(|(v, ok)| {
+            [
+                if *ok { "pass" } else { "fail" }.to_string(),
+                v.id.to_string(),
+                v.year(),
+                v.country.clone().unwrap_or_default(),
+                trunc(v.label.as_deref().unwrap_or(""), 24),
+                trunc(v.catno.as_deref().unwrap_or(""), 16),
+                v.major_formats.as_deref().unwrap_or_default().join(", "),
+                trunc(v.format.as_deref().unwrap_or(""), 40),
+            ]
+        })
+        .collect();
+    let headers = [
+        "",
+        "Release",
+        "Year",
+        "Country",
+        "Label",
+        "Cat#",
+        "Formats",
+        "Descriptions",
+    ];
+    let mut widths = headers.map(|h| h.chars().count());
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    let print_row = |cells: &[String]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(c, w)| format!("{c:<w$}"))
+            .collect();
+        println!("  {}", line.join("  ").trim_end());
+    };
+
+    println!();
+    println!("=== {} — {} versions ===", detail.title, versions.len());
+    println!();
+    print_row(&headers.map(String::from));
+    for row in &rows {
+        print_row(row);
+    }
+    println!();
+    if filters.have_formats() {
+        println!(
+            "{passing} of {} versions pass the format filter.",
+            versions.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Fetch every version of a master, in the order the API returns them.
+fn fetch_master_versions(api: &Discogs, master_id: u64) -> Result<Vec<MasterVersion>, String> {
+    let mut versions: Vec<MasterVersion> = Vec::new();
+    let mut page = 1u32;
+    loop {
+        let p = page.to_string();
+        let path = format!("/masters/{master_id}/versions");
+        let resp: MasterVersionsPage = api.get(
+            "master-versions",
+            &path,
+            &[("page", &p), ("per_page", "100")],
+        )?;
+        eprint!("\r  page {page}/{}\x1b[K", resp.pagination.pages);
+        versions.extend(resp.versions);
+        if page >= resp.pagination.pages {
+            break;
+        }
+        page += 1;
+    }
+    eprintln!();
+    Ok(versions)
+}
+
+/// One pipeline stage's verdict. The run drops an item at the first
+/// failing one; `why` shows them all.
+#[derive(Serialize)]
+struct StageVerdict {
+    stage: Stage,
+    pass: bool,
+    detail: String,
+}
+
+impl StageVerdict {
+    /// A failure with its reason, or a pass described by `ok`.
+    fn new(stage: Stage, failure: Option<String>, ok: String) -> Self {
+        Self {
+            stage,
+            pass: failure.is_none(),
+            detail: failure.unwrap_or(ok),
+        }
+    }
+}
+
+/// The stage and reason the run drops an item for, if any.
+fn first_failure(verdicts: impl IntoIterator<Item = StageVerdict>) -> Option<(Stage, String)> {
+    verdicts
+        .into_iter()
+        .find(|v| !v.pass)
+        .map(|v| (v.stage, v.detail))
+}
+
+fn price_summary(num_for_sale: Option<u32>, lowest_price: Option<f64>, currency: &str) -> String {
+    match (num_for_sale, lowest_price) {
+        (Some(n), Some(p)) if n > 0 => format!("lowest {}, {n} for sale", money(p, currency)),
+        _ => "nothing for sale".into(),
+    }
+}
+
+/// An item as an artist's release list shows it, for the checks the run
+/// makes on that list: the role filter and the inline pre-filter.
+struct ArtistListing {
+    artist: String,
+    kind: &'static str,
+    /// `None` when the artist's list doesn't carry the item at all
+    entry: Option<DedupRelease>,
+}
+
+impl ArtistListing {
+    /// Look the item up on the list of `artist_id`, or of its first
+    /// credited artist.
+    fn fetch(
+        api: &Discogs,
+        artist_id: Option<u64>,
+        credits: &[ArtistCredit],
+        kind: &'static str,
+        id: u64,
+    ) -> Result<Self, String> {
+        let credit = match artist_id {
+            Some(aid) => credits.iter().find(|a| a.id == aid),
+            None => credits.iter().find(|a| a.id != 0),
+        };
+        let Some(aid) = artist_id.or(credit.map(|a| a.id)) else {
+            return Err("no credited artist to check roles against; pass --artist-id".into());
+        };
+        let artist = credit.map_or_else(|| format!("artist {aid}"), |a| a.name.clone());
+        eprintln!("Fetching release list for {artist}...");
+        let (listed, _) = dedup_releases(&fetch_artist_releases(api, aid)?);
+        let entry = listed.into_iter().find(|r| r.kind == kind && r.id == id);
+        Ok(Self {
+            artist,
+            kind,
+            entry,
+        })
+    }
+
+    fn role_verdict(&self, filters: &Filters) -> StageVerdict {
+        match &self.entry {
+            Some(e) => {
+                let roles: Vec<String> = e.roles.iter().map(Role::to_string).collect();
+                StageVerdict::new(
+                    Stage::RoleFilter,
+                    filters.roles.rejection(&e.roles),
+                    format!("{} credited as {}", self.artist, roles.join(", ")),
+                )
+            }
+            None => StageVerdict::new(
+                Stage::RoleFilter,
+                Some(format!(
+                    "not on {}'s release list as a {}",
+                    self.artist, self.kind
+                )),
+                String::new(),
+            ),
+        }
     }
 
-    // ── retry queue: process items that failed with transient errors ──
-    // Each item gets up to 5 total attempts (1 initial + 4 retries).
-    const MAX_ATTEMPTS: u32 = 5;
-    let mut pending = retry_queue;
-    let mut attempt = 2u32; // first retry is attempt 2
+    fn inline_verdict(&self, filters: &Filters) -> StageVerdict {
+        match self.entry.as_ref().and_then(|e| e.format.as_deref()) {
+            Some(fmt) => StageVerdict::new(
+                Stage::InlinePrefilter,
+                filters.inline_rejection(fmt),
+                format!("inline format: {fmt}"),
+            ),
+            None => StageVerdict::new(
+                Stage::InlinePrefilter,
+                None,
+                format!("no inline format on {}'s release list", self.artist),
+            ),
+        }
+    }
+}
 
-    while !pending.is_empty() && attempt <= MAX_ATTEMPTS {
-        eprintln!(
-            "\r  Retrying {} item(s) (attempt {attempt}/{MAX_ATTEMPTS})...\x1b[K",
-            pending.len()
-        );
-        let mut still_failing: Vec<&DedupRelease> = Vec::new();
+/// --exclude-owned/--exclude-wanted against the item's release IDs (a
+/// master's stand-in release and all its versions).
+fn owned_verdicts(
+    api: &Discogs,
+    cli: &Cli,
+    release_ids: &[u64],
+) -> Result<Vec<StageVerdict>, String> {
+    let mut verdicts = Vec::new();
+    if !(cli.exclude_owned || cli.exclude_wanted) {
+        return Ok(verdicts);
+    }
+    let username = fetch_identity(api)?;
+    if cli.exclude_owned {
+        let owned = fetch_collection_ids(api, &username)?;
+        let hit = release_ids.iter().find(|id| owned.contains(id));
+        verdicts.push(StageVerdict::new(
+            Stage::Owned,
+            hit.map(|id| format!("release {id} in collection")),
+            "not in collection".into(),
+        ));
+    }
+    if cli.exclude_wanted {
+        let wanted = fetch_wantlist_notes(api, &username)?;
+        let hit = release_ids.iter().find(|id| wanted.contains_key(id));
+        verdicts.push(StageVerdict::new(
+            Stage::Wanted,
+            hit.map(|id| format!("release {id} in wantlist")),
+            "not in wantlist".into(),
+        ));
+    }
+    Ok(verdicts)
+}
 
-        for item in &pending {
-            eprint!(
-                "\r  [retry {attempt}/{MAX_ATTEMPTS}] {}\x1b[K",
-                trunc(&item.title, 50)
-            );
+/// --year-from/--title-match/--masters-only etc.; `None` without those
+/// filters.
+fn list_verdict(
+    api: &Discogs,
+    filters: &Filters,
+    kind: &str,
+    title: &str,
+    year: Option<u32>,
+) -> Option<StageVerdict> {
+    (!filters.list.is_empty()).then(|| {
+        let year_shown = match year.filter(|&y| y != 0) {
+            Some(y) => y.to_string(),
+            None => "no year".into(),
+        };
+        StageVerdict::new(
+            Stage::ListFilter,
+            filters
+                .list
+                .rejection(kind, title, year, &mut api.stats.borrow_mut()),
+            format!("{kind}, {year_shown}"),
+        )
+    })
+}
 
-            let result = if item.kind == "master" {
-                fetch_master_info(
-                    &api,
-                    item.id,
-                    need_price,
-                    need_detail,
-                    price_limit,
-                    &has,
-                    &not,
-                    &only,
-                    &ignore,
+/// Verdicts on master detail, in pipeline order: genre, then price. A
+/// master over the price limit is a price-skip: nothing more is fetched.
+fn master_detail_verdicts(
+    filters: &Filters,
+    detail: &MasterDetail,
+    currency: &str,
+) -> Vec<StageVerdict> {
+    let mut verdicts = Vec::new();
+    if filters.have_genres() {
+        verdicts.push(StageVerdict::new(
+            Stage::GenreFilter,
+            filters.genre_rejection(&detail.genres, &detail.styles),
+            format!(
+                "{} — {}",
+                detail.genres.join(", "),
+                detail.styles.join(", ")
+            ),
+        ));
+    }
+    verdicts.push(StageVerdict::new(
+        Stage::PriceSkip,
+        filters.price_rejection(detail.num_for_sale, detail.lowest_price, currency),
+        price_summary(detail.num_for_sale, detail.lowest_price, currency),
+    ));
+    verdicts
+}
+
+/// Verdicts on release detail, in pipeline order: version-level filters,
+/// genre, community stats.
+fn release_detail_verdicts(filters: &Filters, detail: &ReleaseDetail) -> Vec<StageVerdict> {
+    let mut verdicts = Vec::new();
+    if filters.have_version_filters() {
+        let pressing = Pressing::of_release(detail);
+        let labels: Vec<&str> = pressing.labels.iter().map(|&(_, name)| name).collect();
+        verdicts.push(StageVerdict::new(
+            Stage::VersionFilter,
+            filters.pressing_rejection(&pressing),
+            format!(
+                "pressed in {} on {}",
+                pressing.country.unwrap_or("unknown"),
+                labels.join(", ")
+            ),
+        ));
+    }
+    if filters.have_genres() {
+        verdicts.push(StageVerdict::new(
+            Stage::GenreFilter,
+            filters.genre_rejection(&detail.genres, &detail.styles),
+            format!(
+                "{} — {}",
+                detail.genres.join(", "),
+                detail.styles.join(", ")
+            ),
+        ));
+    }
+    verdicts.extend(community_verdict(filters, detail.community.as_ref()));
+    verdicts
+}
+
+/// --min-have etc. against community stats; `None` without those filters.
+fn community_verdict(filters: &Filters, community: Option<&Community>) -> Option<StageVerdict> {
+    filters.have_community_filters().then(|| {
+        StageVerdict::new(
+            Stage::CommunityFilter,
+            filters.community_rejection(community),
+            community.map(Community::to_string).unwrap_or_default(),
+        )
+    })
+}
+
+/// `why master|release <id>`: run a single item through each pipeline
+/// stage with the current filters and report every verdict, not just the
+/// first failure.
+fn why_report(
+    api: &Discogs,
+    cli: &Cli,
+    filters: &Filters,
+    kind: ItemKind,
+    id: u64,
+    artist_id: Option<u64>,
+    output: OutputMode,
+) -> Result<(), String> {
+    let mut verdicts: Vec<StageVerdict> = Vec::new();
+
+    let (title, url, artists, release_ids) = match kind {
+        ItemKind::Master => {
+            let mut detail = fetch_master_detail(api, id)?;
+            eprintln!("Master: {} (id {id})", detail.title);
+            if !filters.roles.is_empty() {
+                let listed = ArtistListing::fetch(api, artist_id, &detail.artists, "master", id)?;
+                verdicts.push(listed.role_verdict(filters));
+            }
+            verdicts.extend(list_verdict(
+                api,
+                filters,
+                "master",
+                &detail.title,
+                detail.year,
+            ));
+
+            // ── search pre-filter ───────────────────────────────
+            // The real run searches under every identity of the queried
+            // artist; here the master's own credited artists stand in.
+            let exclude_formats = filters.search_exclude_formats();
+            let search = if filters.have_version_filters() {
+                StageVerdict::new(
+                    Stage::SearchPrefilter,
+                    None,
+                    "skipped: search can't apply version-level filters".into(),
                 )
             } else {
-                release_info(&api, item.id).map(|(formats, lowest_price, num_for_sale, artists)| {
-                    FetchedInfo {
-                        formats,
-                        lowest_price,
-                        num_for_sale,
-                        artists,
-                        release_id: Some(item.id),
+                let mut names: Vec<&str> = detail.artists.iter().map(|a| a.name.as_str()).collect();
+                names.dedup();
+                let mut caught = None;
+                let mut errors = Vec::new();
+                'search: for name in &names {
+                    for fmt in &exclude_formats {
+                        match search_masters_with_format(api, name, fmt) {
+                            Ok(ids) if ids.contains(&id) => {
+                                caught =
+                                    Some(format!("found by search for {fmt} under \"{name}\""));
+                                break 'search;
+                            }
+                            Ok(_) => {}
+                            Err(e) => errors.push(format!("{fmt}: {e}")),
+                        }
                     }
-                })
-            };
-
-            match result {
-                Ok(fetched) => {
-                    api.stats.borrow_mut().requeue_ok += 1;
-                    let url = if item.kind == "master" {
-                        format!("https://www.discogs.com/master/{}", item.id)
-                    } else {
-                        format!("https://www.discogs.com/release/{}", item.id)
-                    };
-                    infos.push(Info {
-                        title: item.title.clone(),
-                        year: item.year.filter(|&y| y != 0),
-                        role: item.role.clone().unwrap_or_else(|| "Main".into()),
-                        formats: fetched.formats,
-                        url,
-                        lowest_price: if need_price {
-                            fetched.lowest_price
-                        } else {
-                            None
-                        },
-                        num_for_sale: if need_price {
-                            fetched.num_for_sale
-                        } else {
-                            None
-                        },
-                        artists: fetched.artists,
-                        release_id: fetched.release_id,
-                    });
-                }
-                Err(e) if is_transient(&e) && attempt < MAX_ATTEMPTS => {
-                    // Still transient and we have more attempts — keep in queue
-                    still_failing.push(item);
-                }
-                Err(e) => {
-                    api.stats.borrow_mut().requeue_fail += 1;
-                    eprintln!(
-                        "\n  warning: giving up on {} {} ({}) after {attempt} attempts: {e}",
-                        item.kind, item.id, item.title
-                    );
                 }
+                let ok = if exclude_formats.is_empty() {
+                    "no --not/--only formats to search for".to_string()
+                } else if errors.is_empty() {
+                    format!("not found by search for {}", exclude_formats.join(", "))
+                } else {
+                    format!("not caught; searches failed: {}", errors.join("; "))
+                };
+                StageVerdict::new(Stage::SearchPrefilter, caught, ok)
+            };
+            verdicts.push(search);
+
+            // ── the run's own version walk and detail checks ────
+            let early_exit = !filters.not.is_empty() || !filters.only.is_empty();
+            let scan = scan_master_versions(api, id, filters, early_exit)?;
+            verdicts.extend(scan.verdicts(filters));
+            // The same stand-in release and prices as fetch_master_info
+            let preferred = scan.preferred.filter(|_| filters.have_version_filters());
+            let priced = price_preferred_version(api, &mut detail, preferred)?;
+            verdicts.extend(master_detail_verdicts(filters, &detail, &api.currency));
+            let stand_in = preferred.or(detail.main_release);
+            if filters.have_community_filters() {
+                let community = match (&detail.community, priced, stand_in) {
+                    (Some(c), _, _) => Some(c.clone()),
+                    (None, Some(r), _) => r.community,
+                    (None, None, Some(rid)) => fetch_release_community(api, rid)?,
+                    (None, None, None) => None,
+                };
+                verdicts.extend(community_verdict(filters, community.as_ref()));
+            }
+            let release_ids = stand_in.into_iter().chain(scan.version_ids).collect();
+            (
+                detail.title,
+                format!("https://www.discogs.com/master/{id}"),
+                detail.artists,
+                release_ids,
+            )
+        }
+        ItemKind::Release => {
+            let path = format!("/releases/{id}");
+            let detail: ReleaseDetail =
+                api.get("release-detail", &path, &[("curr_abbr", &api.currency)])?;
+            eprintln!("Release: {} (id {id})", detail.title);
+            let listed = if !filters.roles.is_empty() || filters.have_inline_filters() {
+                Some(ArtistListing::fetch(
+                    api,
+                    artist_id,
+                    &detail.artists,
+                    "release",
+                    id,
+                )?)
+            } else {
+                None
+            };
+            if let Some(listed) = &listed
+                && !filters.roles.is_empty()
+            {
+                verdicts.push(listed.role_verdict(filters));
+            }
+            verdicts.extend(list_verdict(
+                api,
+                filters,
+                "release",
+                &detail.title,
+                detail.year,
+            ));
+            verdicts.push(StageVerdict::new(
+                Stage::SearchPrefilter,
+                None,
+                "only applies to masters".into(),
+            ));
+            if let Some(listed) = &listed
+                && filters.have_inline_filters()
+            {
+                verdicts.push(listed.inline_verdict(filters));
             }
+            verdicts.extend(release_detail_verdicts(filters, &detail));
+
+            // ── format and price filters, as in select_hits ─────
+            let mut formats = BTreeSet::new();
+            let mut descriptions = BTreeSet::new();
+            for e in detail.formats.into_iter().flatten() {
+                formats.insert(e.name);
+                descriptions.extend(e.descriptions);
+            }
+            let listed: Vec<&str> = formats.iter().map(String::as_str).collect();
+            verdicts.push(StageVerdict::new(
+                Stage::FormatFilter,
+                filters.format_rejection(&formats),
+                format!("formats: {}", listed.join(", ")),
+            ));
+            if filters.have_descriptions() {
+                let listed: Vec<&str> = descriptions.iter().map(String::as_str).collect();
+                verdicts.push(StageVerdict::new(
+                    Stage::FormatFilter,
+                    filters.description_rejection(&descriptions),
+                    format!("descriptions: {}", listed.join(", ")),
+                ));
+            }
+            verdicts.push(StageVerdict::new(
+                Stage::PriceFilter,
+                filters.price_rejection(detail.num_for_sale, detail.lowest_price, &api.currency),
+                price_summary(detail.num_for_sale, detail.lowest_price, &api.currency),
+            ));
+            (
+                detail.title,
+                format!("https://www.discogs.com/release/{id}"),
+                detail.artists,
+                vec![id],
+            )
         }
+    };
+    verdicts.extend(owned_verdicts(api, cli, &release_ids)?);
+
+    let dropped_at = verdicts.iter().find(|v| !v.pass).map(|v| v.stage);
+    if output == OutputMode::Json {
+        let report = serde_json::json!({
+            "kind": format!("{kind:?}").to_lowercase(),
+            "id": id,
+            "title": title,
+            "artists": format_artists(&artists),
+            "url": url,
+            "pass": dropped_at.is_none(),
+            "stages": verdicts,
+        });
+        let text =
+            serde_json::to_string_pretty(&report).map_err(|e| format!("JSON output: {e}"))?;
+        println!("{text}");
+        return Ok(());
+    }
 
-        pending = still_failing;
-        attempt += 1;
+    println!();
+    println!("=== {} — {} ===", format_artists(&artists), title);
+    println!("  {url}");
+    println!();
+    let width = verdicts
+        .iter()
+        .map(|v| v.stage.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for v in &verdicts {
+        let mark = if v.pass { "pass" } else { "FAIL" };
+        let stage = v.stage.to_string();
+        println!("  {stage:<width$}  {mark}  {}", v.detail);
+    }
+    println!();
+    match dropped_at {
+        None => println!("Would be included."),
+        Some(stage) => println!("Would be excluded at {stage}."),
     }
 
-    eprintln!("\r  Done.\x1b[K");
+    Ok(())
+}
+
+/// Apply the final format/price filter and owned/wanted exclusions to one
+/// artist's results, sorted by year.
+fn select_hits<'a>(
+    report: &'a ArtistReport,
+    filters: &Filters,
+    cli: &Cli,
+    owned: &HashSet<u64>,
+    wanted: &HashMap<u64, String>,
+    currency: &str,
+) -> (Vec<&'a Info>, Vec<Rejection>) {
+    let mut rejected = report.rejected.clone();
 
     // ── apply filter ────────────────────────────────────────────
-    let mut hits: Vec<&Info> = infos
+    let mut hits: Vec<&Info> = report
+        .infos
         .iter()
         .filter(|r| {
-            let lc: HashSet<String> = r
-                .formats
-                .iter()
-                .map(|f| f.to_lowercase())
-                .filter(|f| !ignore.contains(f))
-                .collect();
-            let has_ok = has.iter().all(|h| lc.contains(h));
-            let not_ok = !not.iter().any(|n| lc.contains(n));
-            let only_ok =
-                only.is_empty() || (!lc.is_empty() && lc.iter().all(|f| only.contains(f)));
-
-            let price_ok = match price_limit {
+            let why = r
+                .rejection
+                .clone()
+                .or_else(|| {
+                    filters
+                        .format_rejection(&r.formats)
+                        .map(|f| (Stage::FormatFilter, f))
+                })
+                .or_else(|| {
+                    let descriptions = r.descriptions.as_ref()?;
+                    filters
+                        .description_rejection(descriptions)
+                        .map(|d| (Stage::FormatFilter, d))
+                })
+                .or_else(|| {
+                    filters
+                        .price_rejection(r.num_for_sale, r.lowest_price, currency)
+                        .map(|p| (Stage::PriceFilter, p))
+                });
+            match why {
+                Some((stage, detail)) => {
+                    rejected.push(Rejection::from_info(r, stage, detail));
+                    false
+                }
                 None => true,
-                Some(limit) => match (r.num_for_sale, r.lowest_price) {
-                    (Some(n), Some(p)) if n > 0 => p <= limit,
-                    _ => false,
-                },
-            };
-
-            has_ok && not_ok && only_ok && price_ok
+            }
         })
         .collect();
 
+    // ── --exclude-owned / --exclude-wanted ──────────────────────
+    if cli.exclude_owned || cli.exclude_wanted {
+        let mut excluded_owned = 0usize;
+        let mut excluded_wanted = 0usize;
+        hits.retain(|r| {
+            let mut ids = r.release_id.iter().chain(&r.version_ids);
+            if cli.exclude_owned
+                && let Some(id) = ids.clone().find(|id| owned.contains(id))
+            {
+                excluded_owned += 1;
+                let detail = format!("release {id} in collection");
+                rejected.push(Rejection::from_info(r, Stage::Owned, detail));
+                false
+            } else if cli.exclude_wanted
+                && let Some(id) = ids.find(|id| wanted.contains_key(id))
+            {
+                excluded_wanted += 1;
+                let detail = format!("release {id} in wantlist");
+                rejected.push(Rejection::from_info(r, Stage::Wanted, detail));
+                false
+            } else {
+                true
+            }
+        });
+        if excluded_owned > 0 {
+            eprintln!("--exclude-owned: dropped {excluded_owned} releases already in collection");
+        }
+        if excluded_wanted > 0 {
+            eprintln!("--exclude-wanted: dropped {excluded_wanted} releases already in wantlist");
+        }
+    }
+
+    // Year first so that ties under any other key stay in year order
     hits.sort_by_key(|r| r.year.unwrap_or(u32::MAX));
+    let key = cli.sort.unwrap_or_default();
+    let order = cli.sort_order.unwrap_or(key.default_order());
+    if (key, order) != (SortKey::Year, SortOrder::Asc) {
+        hits.sort_by(|a, b| key.compare(order, a, b));
+    }
+    rejected.sort_by_key(|r| (r.stage, r.year.unwrap_or(u32::MAX)));
 
-    // ── print results ───────────────────────────────────────────
+    (hits, rejected)
+}
+
+/// Print the --explain section: one line per rejected item, grouped by
+/// the stage that dropped it.
+fn print_rejections(rejected: &[Rejection]) {
+    if rejected.is_empty() {
+        return;
+    }
+    let mut counts: BTreeMap<Stage, usize> = BTreeMap::new();
+    for r in rejected {
+        *counts.entry(r.stage).or_default() += 1;
+    }
+    let summary: Vec<String> = counts.iter().map(|(s, n)| format!("{s} {n}")).collect();
+    println!("  Rejected ({}): {}", rejected.len(), summary.join(", "));
+    for r in rejected {
+        let year = r.year.map(|y| format!(" ({y})")).unwrap_or_default();
+        println!("    {}{year} — {}: {}", r.title, r.stage, r.detail);
+        println!("      {}", r.url);
+    }
     println!();
+}
 
-    if has.is_empty() && not.is_empty() && only.is_empty() && price_limit.is_none() {
+/// Print the "=== Releases ... ===" heading describing the active filters.
+fn print_header(filters: &Filters, currency: &str) {
+    if filters.has.is_empty()
+        && filters.not.is_empty()
+        && filters.only.is_empty()
+        && filters.price_limit.is_none()
+    {
         println!("=== All releases ===");
     } else {
         print!("=== Releases");
-        if !only.is_empty() {
-            let mut v: Vec<_> = only.iter().map(String::as_str).collect();
+        if !filters.only.is_empty() {
+            let mut v: Vec<_> = filters.only.iter().map(String::as_str).collect();
             v.sort();
             print!(" only [{}]", v.join(", "));
         }
-        if !has.is_empty() {
-            let mut v: Vec<_> = has.iter().map(String::as_str).collect();
+        if !filters.has.is_empty() {
+            let mut v: Vec<_> = filters.has.iter().map(String::as_str).collect();
             v.sort();
             print!(" with [{}]", v.join(", "));
         }
-        if !not.is_empty() {
-            let mut v: Vec<_> = not.iter().map(String::as_str).collect();
+        if !filters.not.is_empty() {
+            let mut v: Vec<_> = filters.not.iter().map(String::as_str).collect();
             v.sort();
             print!(" without [{}]", v.join(", "));
         }
-        if !ignore.is_empty() {
-            let mut v: Vec<_> = ignore.iter().map(String::as_str).collect();
+        if !filters.ignore.is_empty() {
+            let mut v: Vec<_> = filters.ignore.iter().map(String::as_str).collect();
             v.sort();
             print!(" ignoring [{}]", v.join(", "));
         }
-        if let Some(limit) = price_limit {
-            print!(" under ${:.2}", limit);
+        if let Some(limit) = filters.price_limit {
+            print!(" under {}", money(limit, currency));
         }
         println!(" ===");
     }
     println!();
+}
 
+/// Print one block per hit, under a heading per group with --group-by.
+/// Groups are listed in heading order, hits within each keep their --sort
+/// order.
+fn print_hits(
+    hits: &[&Info],
+    main_artist: &str,
+    filters: &Filters,
+    currency: &str,
+    group_by: Option<GroupBy>,
+) {
     if hits.is_empty() {
         println!("  (none)");
+        return;
+    }
+    let Some(group_by) = group_by else {
+        for r in hits {
+            print_hit(r, main_artist, filters, currency);
+        }
+        return;
+    };
+    // (missing, heading) so that the group without a value comes last
+    let mut groups: BTreeMap<(bool, String), Vec<&Info>> = BTreeMap::new();
+    for r in hits {
+        let key = match group_by.heading(r, filters) {
+            Some(heading) => (false, heading),
+            None => (true, group_by.missing().to_string()),
+        };
+        groups.entry(key).or_default().push(r);
+    }
+    for ((_, heading), members) in groups {
+        println!("  ── {heading} ({}) ──", members.len());
+        println!();
+        for r in members {
+            print_hit(r, main_artist, filters, currency);
+        }
+    }
+}
+
+/// Print one hit: title, artists, formats, price, URL.
+/// Hits found under a related identity rather than `main_artist` are
+/// annotated with the identities that credit them.
+fn print_hit(r: &Info, main_artist: &str, filters: &Filters, currency: &str) {
+    let yr = r.year.map(|y| format!(" ({y})")).unwrap_or_default();
+    let role = if r.roles.iter().all(|x| *x == Role::Main) {
+        String::new()
     } else {
-        for r in &hits {
-            let yr = r.year.map(|y| format!(" ({y})")).unwrap_or_default();
-            let role = if r.role == "Main" {
-                String::new()
-            } else {
-                format!(" [{}]", r.role)
-            };
-            let visible: Vec<_> = r
-                .formats
-                .iter()
-                .filter(|f| !ignore.contains(&f.to_lowercase()))
-                .cloned()
-                .collect();
-            let fmts = if visible.is_empty() {
-                "(unknown)".to_string()
-            } else {
-                visible.join(", ")
-            };
+        let names: Vec<_> = r.roles.iter().map(Role::to_string).collect();
+        format!(" [{}]", names.join(", "))
+    };
+    let visible: Vec<_> = r
+        .formats
+        .iter()
+        .filter(|f| !filters.ignore.contains(&f.to_lowercase()))
+        .cloned()
+        .collect();
+    let fmts = if visible.is_empty() {
+        "(unknown)".to_string()
+    } else {
+        visible.join(", ")
+    };
 
-            println!("  {}{yr}{role}", r.title);
-            let by = format_artists(&r.artists);
-            if !by.is_empty() {
-                println!("    by {by}");
-            }
-            print!("    Formats: {fmts}");
-            if let (Some(nfs), Some(lp)) = (r.num_for_sale, r.lowest_price) {
-                if nfs > 0 {
-                    print!("  |  ${:.2} ({} for sale)", lp, nfs);
-                } else {
-                    print!("  |  none for sale");
-                }
-            }
-            println!();
-            println!("    {}", r.url);
-            println!();
+    println!("  {}{yr}{role}", r.title);
+    let by = format_artists(&r.artists);
+    if !by.is_empty() {
+        println!("    by {by}");
+    }
+    if r.credited.iter().any(|c| c != main_artist) {
+        println!("    credited as: {}", r.credited.join(", "));
+    }
+    print!("    Formats: {fmts}");
+    if let (Some(nfs), Some(lp)) = (r.num_for_sale, r.lowest_price) {
+        if nfs > 0 {
+            print!("  |  {} ({} for sale)", money(lp, currency), nfs);
+        } else {
+            print!("  |  none for sale");
         }
     }
+    if let Some(prev) = &r.last_seen {
+        print!("  |  {}", prev.change_to(r.price(), currency));
+    }
+    println!();
+    if let Some(label) = &r.label {
+        match &r.catno {
+            Some(catno) => println!("    Label: {label} – {catno}"),
+            None => println!("    Label: {label}"),
+        }
+    }
+    let flags: Vec<&str> = FLAGGED_DESCRIPTIONS
+        .iter()
+        .map(|&(d, _)| d)
+        .filter(|d| r.descriptions.iter().flatten().any(|x| x == d))
+        .collect();
+    if !flags.is_empty() {
+        println!("    Flagged: {}", flags.join(", "));
+    }
+    if !r.genres.is_empty() || !r.styles.is_empty() {
+        let mut line = r.genres.join(", ");
+        if !r.styles.is_empty() {
+            line = format!("{line} — {}", r.styles.join(", "));
+        }
+        println!("    Genre: {}", line.trim_start_matches(" — "));
+    }
+    if let Some(c) = &r.community {
+        println!("    Community: {c}");
+    }
+    if filters.have_version_filters() && !r.countries.is_empty() {
+        let countries: Vec<_> = r.countries.iter().map(String::as_str).collect();
+        println!("    Pressed in: {}", countries.join(", "));
+    }
+    println!("    {}", r.url);
+    println!();
+}
 
-    println!("{} matching / {} total.", hits.len(), infos.len());
+// ── --budget: best purchase set under a total spend ─────────────
 
-    // ── add to wantlist ─────────────────────────────────────────
-    if cli.add_to_wantlist && !hits.is_empty() {
-        eprintln!();
-        eprintln!("Adding {} item(s) to wantlist...", hits.len());
+/// Hits picked by --budget, in listing order.
+struct ShoppingList<'a> {
+    budget: f64,
+    objective: Objective,
+    items: Vec<&'a Info>,
+    total: f64,
+    /// Summed objective value of `items`
+    value: u64,
+}
 
-        let username = fetch_identity(&api)?;
-        if cli.verbose {
-            eprintln!("  Authenticated as: {username}");
+/// Largest knapsack table (budget steps × hits) before --budget moves from
+/// cents to coarser steps.
+const BUDGET_MAX_CELLS: usize = 50_000_000;
+
+/// 0/1 knapsack over the hits' lowest prices: the set with the highest
+/// total objective value whose prices add up to at most `budget`, and the
+/// cheapest such set on ties. Hits with nothing for sale can't be bought
+/// and are left out; a hit listed under several artists counts once.
+fn plan_budget<'a>(
+    selected: &[Selection<'a>],
+    budget: f64,
+    objective: Objective,
+    priorities: &HashMap<u64, u64>,
+) -> ShoppingList<'a> {
+    let mut seen = HashSet::new();
+    let candidates: Vec<(&Info, f64, u64)> = selected
+        .iter()
+        .flat_map(|(_, hits, _)| hits.iter().copied())
+        .filter(|r| seen.insert(r.url.as_str()))
+        .filter_map(|r| {
+            let price = r.price()?;
+            let value = match objective {
+                Objective::Count => 1,
+                Objective::Want => r.community.as_ref().map_or(0, |c| u64::from(c.want)),
+                Objective::Priority => std::iter::once(&r.id)
+                    .chain(&r.release_id)
+                    .chain(&r.version_ids)
+                    .find_map(|id| priorities.get(id).copied())
+                    .unwrap_or(1),
+            };
+            Some((r, price, value))
+        })
+        .collect();
+
+    // Prices round up and the budget down, so the pick never overspends
+    let mut step = 0.01;
+    while ((budget / step) as usize).saturating_mul(candidates.len() + 1) > BUDGET_MAX_CELLS {
+        step *= 10.0;
+    }
+    let cap = (budget / step + 1e-9).floor() as usize;
+    let weights: Vec<usize> = candidates
+        .iter()
+        .map(|&(_, price, _)| (price / step - 1e-9).ceil().max(0.0) as usize)
+        .collect();
+
+    // best[c]: highest value spending at most c steps; took[i][c]: whether
+    // hit i was part of that when it was considered
+    let width = cap + 1;
+    let mut best = vec![0u64; width];
+    let mut took = vec![false; candidates.len() * width];
+    for (i, (&(_, _, value), &w)) in candidates.iter().zip(&weights).enumerate() {
+        if w > cap {
+            continue;
         }
+        for c in (w..=cap).rev() {
+            if best[c - w] + value > best[c] {
+                best[c] = best[c - w] + value;
+                took[i * width + c] = true;
+            }
+        }
+    }
 
-        eprintln!("  Fetching existing wantlist...");
-        let existing_notes = fetch_wantlist_notes(&api, &username)?;
-        if cli.verbose {
-            eprintln!("  Wantlist has {} items", existing_notes.len());
+    // The smallest spend that reaches the best value is the cheapest set
+    let mut c = best.iter().position(|&v| v == best[cap]).unwrap_or(0);
+    let mut picked = Vec::new();
+    for i in (0..candidates.len()).rev() {
+        if took[i * width + c] {
+            picked.push(i);
+            c -= weights[i];
         }
+    }
+    picked.reverse();
+
+    ShoppingList {
+        budget,
+        objective,
+        total: picked.iter().map(|&i| candidates[i].1).sum(),
+        value: picked.iter().map(|&i| candidates[i].2).sum(),
+        items: picked.iter().map(|&i| candidates[i].0).collect(),
+    }
+}
+
+/// Print the --budget shopping list with its total.
+fn print_shopping_list(list: &ShoppingList, currency: &str) {
+    println!();
+    println!(
+        "=== Shopping list: {} budget, maximizing {} ===",
+        money(list.budget, currency),
+        list.objective
+    );
+    println!();
+    if list.items.is_empty() {
+        println!("  (nothing for sale within budget)");
+    }
+    for r in &list.items {
+        let yr = r.year.map(|y| format!(" ({y})")).unwrap_or_default();
+        let price = money(r.lowest_price.unwrap_or_default(), currency);
+        println!("  {price:>10}  {}{yr}", r.title);
+        println!("              {}", r.url);
+    }
+    println!();
+    let value = match list.objective {
+        Objective::Count => String::new(),
+        objective => format!(", {objective} {}", list.value),
+    };
+    println!(
+        "Total: {} for {} records{value} ({} left).",
+        money(list.total, currency),
+        list.items.len(),
+        money(list.budget - list.total, currency)
+    );
+}
 
-        let query_summary = build_query_summary(&has, &not, &only, &ignore, price_limit);
-        let date = today_str();
-        let new_tag = FilterTag {
-            query: query_summary,
-            artist: artist_detail.name.clone(),
-            date,
+/// Add hits to the wantlist, tagging each item's notes with `tag`.
+/// `existing_notes` is updated so later artists in a batch see the new notes.
+fn add_hits_to_wantlist(
+    api: &Discogs,
+    username: &str,
+    hits: &[&Info],
+    existing_notes: &mut HashMap<u64, String>,
+    tag: &FilterTag,
+) {
+    eprintln!();
+    eprintln!("Adding {} item(s) to wantlist...", hits.len());
+
+    let mut added = 0u32;
+    let mut skipped = 0u32;
+    for r in hits {
+        let release_id = match r.release_id {
+            Some(id) => id,
+            None => {
+                eprintln!(
+                    "  warning: no release ID for '{}', skipping wantlist add",
+                    r.title
+                );
+                skipped += 1;
+                continue;
+            }
         };
 
-        let mut added = 0u32;
-        let mut skipped = 0u32;
-        for r in &hits {
-            let release_id = match r.release_id {
-                Some(id) => id,
-                None => {
-                    eprintln!(
-                        "  warning: no release ID for '{}', skipping wantlist add",
-                        r.title
-                    );
-                    skipped += 1;
-                    continue;
-                }
-            };
+        let old_notes = existing_notes
+            .get(&release_id)
+            .map(|s| s.as_str())
+            .unwrap_or("");
+        let new_notes = update_notes(old_notes, tag);
+
+        let path = format!("/users/{username}/wants/{release_id}");
 
-            let old_notes = existing_notes
-                .get(&release_id)
-                .map(|s| s.as_str())
-                .unwrap_or("");
-            let new_notes = update_notes(old_notes, &new_tag);
+        eprint!(
+            "\r  [{}/{}] {}\x1b[K",
+            added + skipped + 1,
+            hits.len(),
+            trunc(&r.title, 50)
+        );
+
+        // PUT ensures the item exists (creates if new, no-op if exists).
+        // POST then sets the notes (PUT ignores notes in the body).
+        let empty = serde_json::json!({});
+        let notes_body = serde_json::json!({ "notes": new_notes });
+        match api
+            .request("PUT", "wantlist-put", &path, &empty)
+            .and_then(|_| api.request("POST", "wantlist-post", &path, &notes_body))
+        {
+            Ok(_) => {
+                existing_notes.insert(release_id, new_notes);
+                added += 1;
+            }
+            Err(e) => {
+                eprintln!("\n  warning: failed to add '{}' to wantlist: {e}", r.title);
+                skipped += 1;
+            }
+        }
+    }
+
+    eprintln!("\r  Wantlist: {added} added/updated, {skipped} skipped.\x1b[K");
+}
 
-            let path = format!("/users/{username}/wants/{release_id}");
+/// Add hits to a collection folder, tagging each instance's notes field.
+fn add_hits_to_collection(
+    api: &Discogs,
+    username: &str,
+    (folder_id, field_id): (u64, u64),
+    hits: &[&Info],
+    tag: &FilterTag,
+) {
+    eprintln!();
+    eprintln!(
+        "Adding {} item(s) to collection folder {folder_id}...",
+        hits.len()
+    );
 
-            eprint!(
-                "\r  [{}/{}] {}\x1b[K",
-                added + skipped + 1,
-                hits.len(),
-                trunc(&r.title, 50)
+    let mut added = 0u32;
+    let mut updated = 0u32;
+    let mut skipped = 0u32;
+    for (i, r) in hits.iter().enumerate() {
+        let Some(release_id) = r.release_id else {
+            eprintln!(
+                "  warning: no release ID for '{}', skipping collection add",
+                r.title
             );
+            skipped += 1;
+            continue;
+        };
 
-            // PUT ensures the item exists (creates if new, no-op if exists).
-            // POST then sets the notes (PUT ignores notes in the body).
-            let empty = serde_json::json!({});
-            let notes_body = serde_json::json!({ "notes": new_notes });
-            match api
-                .request("PUT", "wantlist-put", &path, &empty)
-                .and_then(|_| api.request("POST", "wantlist-post", &path, &notes_body))
-            {
-                Ok(()) => added += 1,
-                Err(e) => {
-                    eprintln!("\n  warning: failed to add '{}' to wantlist: {e}", r.title);
-                    skipped += 1;
-                }
+        eprint!(
+            "\r  [{}/{}] {}\x1b[K",
+            i + 1,
+            hits.len(),
+            trunc(&r.title, 50)
+        );
+
+        match add_to_collection_folder(api, username, folder_id, field_id, release_id, tag) {
+            Ok(true) => added += 1,
+            Ok(false) => updated += 1,
+            Err(e) => {
+                eprintln!(
+                    "\n  warning: failed to add '{}' to collection: {e}",
+                    r.title
+                );
+                skipped += 1;
             }
         }
-
-        eprintln!("\r  Wantlist: {added} added/updated, {skipped} skipped.\x1b[K");
     }
 
-    api.print_stats(dedup_saved);
-
-    Ok(())
+    eprintln!("\r  Collection: {added} added, {updated} updated, {skipped} skipped.\x1b[K");
 }
 
 // ── dedup releases by (kind, id), merge roles ──────────────────
@@ -1190,8 +4736,10 @@ struct DedupRelease {
     kind: String,
     title: String,
     year: Option<u32>,
-    role: Option<String>,
+    /// All roles the artist is credited with (missing role = Main)
+    roles: BTreeSet<Role>,
     format: Option<String>,
+    credited: Vec<String>,
 }
 
 fn dedup_releases(all: &[ArtistRelease]) -> (Vec<DedupRelease>, usize) {
@@ -1204,13 +4752,9 @@ fn dedup_releases(all: &[ArtistRelease]) -> (Vec<DedupRelease>, usize) {
         if let Some(&idx) = seen.get(&key) {
             // Merge role info
             let existing = &mut out[idx];
-            if let Some(new_role) = &r.role {
-                if let Some(ref mut old_role) = existing.role {
-                    if !old_role.contains(new_role.as_str()) {
-                        old_role.push_str(", ");
-                        old_role.push_str(new_role);
-                    }
-                }
+            existing.roles.insert(r.role.clone().unwrap_or(Role::Main));
+            if !existing.credited.contains(&r.credited_as) {
+                existing.credited.push(r.credited_as.clone());
             }
             dupes += 1;
         } else {
@@ -1220,8 +4764,9 @@ fn dedup_releases(all: &[ArtistRelease]) -> (Vec<DedupRelease>, usize) {
                 kind: r.kind.clone(),
                 title: r.title.clone(),
                 year: r.year,
-                role: r.role.clone(),
+                roles: BTreeSet::from([r.role.clone().unwrap_or(Role::Main)]),
                 format: r.format.clone(),
+                credited: vec![r.credited_as.clone()],
             });
         }
     }
@@ -1231,7 +4776,56 @@ fn dedup_releases(all: &[ArtistRelease]) -> (Vec<DedupRelease>, usize) {
 
 // ── interactive artist picker ──────────────────────────────────
 
-fn pick_artist(api: &Discogs, name: &str) -> Result<u64, String> {
+/// Read an --artists-from list: one name or numeric ID per line.
+/// Blank lines and lines starting with '#' are skipped.
+fn read_artist_list(src: &str) -> Result<Vec<ArtistRef>, String> {
+    let text = if src == "-" {
+        io::read_to_string(io::stdin()).map_err(|e| format!("stdin: {e}"))?
+    } else {
+        std::fs::read_to_string(src).map_err(|e| format!("{src}: {e}"))?
+    };
+
+    let artists: Vec<ArtistRef> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| match l.parse() {
+            Ok(id) => ArtistRef::Id(id),
+            Err(_) => ArtistRef::Name(l.to_string()),
+        })
+        .collect();
+
+    if artists.is_empty() {
+        return Err(format!("{src}: no artists listed"));
+    }
+    Ok(artists)
+}
+
+/// Resolve an artist name to an ID according to `policy`.
+///
+/// A name resolved by an explicit choice (the prompt, or `--pick N` for a
+/// single artist) is stored in the alias map and reused on later runs
+/// without searching again. In `batch` mode (--artists-from) there is no
+/// prompt, and `--pick N` applies to every name, so isn't remembered.
+fn pick_artist(
+    api: &Discogs,
+    name: &str,
+    policy: PickPolicy,
+    batch: bool,
+    aliases: &mut AliasMap,
+) -> Result<u64, String> {
+    // Prompting needs a terminal, and a batch list may itself be on stdin
+    let can_prompt = !batch && io::stdin().is_terminal();
+
+    // --pick N means "choose again", and --pick exact/fail must not be
+    // satisfied by an earlier choice, so only these use the remembered pick
+    if matches!(policy, PickPolicy::Prompt | PickPolicy::First)
+        && let Some(id) = aliases.get(name)
+    {
+        eprintln!("Using remembered pick for \"{name}\": id {id}");
+        return Ok(id);
+    }
+
     eprintln!("Searching for \"{name}\"...");
 
     let resp: SearchResponse = api.get(
@@ -1240,78 +4834,713 @@ fn pick_artist(api: &Discogs, name: &str) -> Result<u64, String> {
         &[("q", name), ("type", "artist"), ("per_page", "10")],
     )?;
 
-    match resp.results.len() {
-        0 => Err(format!("No artists found for \"{name}\"")),
-        1 => {
+    // Check for exactly one exact case-insensitive match
+    let name_lower = name.to_lowercase();
+    let exact_matches: Vec<usize> = resp
+        .results
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.title.to_lowercase() == name_lower)
+        .map(|(i, _)| i)
+        .collect();
+    let exact = if exact_matches.len() == 1 {
+        Some(exact_matches[0])
+    } else {
+        None
+    };
+
+    match (resp.results.len(), policy) {
+        (0, _) => return Err(format!("No artists found for \"{name}\"")),
+        (1, PickPolicy::Prompt | PickPolicy::First | PickPolicy::Fail) => {
             let a = &resp.results[0];
             eprintln!("Found: {} (id {})", a.title, a.id);
-            Ok(a.id)
+            return Ok(a.id);
         }
-        _ => {
-            // Check for exactly one exact case-insensitive match
-            let name_lower = name.to_lowercase();
-            let exact_matches: Vec<usize> = resp
-                .results
-                .iter()
-                .enumerate()
-                .filter(|(_, a)| a.title.to_lowercase() == name_lower)
-                .map(|(i, _)| i)
-                .collect();
-            let auto_pick = if exact_matches.len() == 1 {
-                Some(exact_matches[0])
+        _ => {}
+    }
+
+    let auto_pick = match policy {
+        PickPolicy::Prompt | PickPolicy::Exact => exact,
+        PickPolicy::First => exact.or(Some(0)),
+        PickPolicy::Fail => None,
+        PickPolicy::Index(n) if n <= resp.results.len() => Some(n - 1),
+        PickPolicy::Index(n) => {
+            return Err(format!(
+                "--pick {n} is out of range; \"{name}\" has {} matches:\n{}",
+                resp.results.len(),
+                candidate_list(&resp.results)
+            ));
+        }
+    };
+
+    let will_prompt = auto_pick.is_none() && policy == PickPolicy::Prompt && can_prompt;
+    if resp.results.len() > 1 && !will_prompt {
+        eprintln!("\nMultiple matches:\n");
+        for (i, a) in resp.results.iter().enumerate() {
+            let url = match a.uri.as_deref() {
+                Some(u) if u.starts_with("http") => u.to_string(),
+                Some(u) => format!("https://www.discogs.com{u}"),
+                None => format!("https://www.discogs.com/artist/{}", a.id),
+            };
+            let marker = if auto_pick == Some(i) {
+                "  ← auto-selected"
             } else {
-                None
+                ""
             };
+            eprintln!("  {}: {} (id {}){marker}", i + 1, a.title, a.id);
+            eprintln!("     {url}");
+        }
+        eprintln!();
+    }
 
-            eprintln!("\nMultiple matches:\n");
-            for (i, a) in resp.results.iter().enumerate() {
-                let url = match a.uri.as_deref() {
-                    Some(u) if u.starts_with("http") => u.to_string(),
-                    Some(u) => format!("https://www.discogs.com{u}"),
-                    None => format!("https://www.discogs.com/artist/{}", a.id),
-                };
-                let marker = if auto_pick == Some(i) {
-                    "  ← exact match, auto-selected"
-                } else {
-                    ""
-                };
-                eprintln!("  {}: {} (id {}){marker}", i + 1, a.title, a.id);
-                eprintln!("     {url}");
+    if let Some(idx) = auto_pick {
+        let a = &resp.results[idx];
+        eprintln!("Auto-selected: {} (id {})", a.title, a.id);
+        if let PickPolicy::Index(_) = policy
+            && !batch
+        {
+            aliases.remember(name, a.id);
+        }
+        return Ok(a.id);
+    }
+
+    if policy != PickPolicy::Prompt || !can_prompt {
+        let why = match policy {
+            PickPolicy::Exact => "no unique exact match",
+            PickPolicy::Prompt if batch => "batch mode can't prompt",
+            PickPolicy::Prompt => "stdin is not a terminal, refusing to prompt",
+            _ => "--pick fail",
+        };
+        return Err(format!(
+            "\"{name}\" is ambiguous ({why}); use --id or --pick N:\n{}",
+            candidate_list(&resp.results)
+        ));
+    }
+
+    let id = prompt_artist(api, name, resp)?;
+    aliases.remember(name, id);
+    Ok(id)
+}
+
+/// Details shown for each candidate in the interactive picker.
+struct CandidateInfo {
+    realname: Option<String>,
+    profile: String,
+    members: Vec<String>,
+    groups: Vec<String>,
+}
+
+/// Interactive disambiguation view. Shows profile excerpt, real name,
+/// members/groups and release count for each candidate, and accepts:
+///   N      pick candidate N
+///   n / p  next / previous page of search results
+///   text   narrow the list to candidates mentioning all entered words
+///   (empty) clear the filter
+///   q      give up
+fn prompt_artist(api: &Discogs, name: &str, first: SearchResponse) -> Result<u64, String> {
+    let mut page = 1u32;
+    let mut pages = first.pagination.pages.max(1);
+    let mut results = first.results;
+    let mut details: HashMap<u64, CandidateInfo> = HashMap::new();
+    let mut counts: HashMap<u64, Option<u32>> = HashMap::new();
+    let mut terms: Vec<String> = Vec::new();
+
+    loop {
+        for a in &results {
+            details.entry(a.id).or_insert_with(|| {
+                eprint!("\r  Loading details for {}...\x1b[K", trunc(&a.title, 40));
+                fetch_candidate_info(api, a.id)
+            });
+        }
+
+        let shown: Vec<&SearchHit> = results
+            .iter()
+            .filter(|a| {
+                let d = &details[&a.id];
+                let haystack = format!(
+                    "{} {} {} {} {}",
+                    a.title,
+                    d.realname.as_deref().unwrap_or(""),
+                    d.profile,
+                    d.members.join(" "),
+                    d.groups.join(" ")
+                )
+                .to_lowercase();
+                terms.iter().all(|t| haystack.contains(t.as_str()))
+            })
+            .collect();
+
+        eprintln!("\r\x1b[K");
+        eprint!("Matches for \"{name}\" (page {page}/{pages}");
+        if !terms.is_empty() {
+            eprint!(", filter: {}", terms.join(" "));
+        }
+        eprintln!("):\n");
+        for (i, a) in shown.iter().enumerate() {
+            let d = &details[&a.id];
+            let count = counts
+                .entry(a.id)
+                .or_insert_with(|| fetch_release_count(api, a.id))
+                .map(|n| format!("  — {n} releases"))
+                .unwrap_or_default();
+            eprintln!("  {}: {} (id {}){count}", i + 1, a.title, a.id);
+            if let Some(real) = &d.realname {
+                eprintln!("     Real name: {real}");
+            }
+            if !d.groups.is_empty() {
+                eprintln!("     Groups: {}", trunc(&d.groups.join(", "), 70));
             }
+            if !d.members.is_empty() {
+                eprintln!("     Members: {}", trunc(&d.members.join(", "), 70));
+            }
+            if !d.profile.is_empty() {
+                eprintln!("     {}", trunc(&d.profile, 90));
+            }
+            eprintln!("     https://www.discogs.com/artist/{}", a.id);
+        }
+        if shown.is_empty() {
+            eprintln!("  no matches on this page");
+        }
 
-            if let Some(idx) = auto_pick {
-                let a = &resp.results[idx];
-                eprintln!();
-                eprintln!("Auto-selected: {} (id {})", a.title, a.id);
-                return Ok(a.id);
+        eprintln!();
+        let mut nav = Vec::new();
+        if page < pages {
+            nav.push("n=next page");
+        }
+        if page > 1 {
+            nav.push("p=prev page");
+        }
+        nav.push("text=filter");
+        if !terms.is_empty() {
+            nav.push("empty=clear filter");
+        }
+        nav.push("q=quit");
+        if shown.is_empty() {
+            eprint!("No matches ({}): ", nav.join(", "));
+        } else {
+            eprint!("Pick [1-{}] ({}): ", shown.len(), nav.join(", "));
+        }
+        io::stderr().flush().unwrap();
+
+        let mut buf = String::new();
+        let n = io::stdin()
+            .lock()
+            .read_line(&mut buf)
+            .map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("no selection made (end of input)".into());
+        }
+        let input = buf.trim();
+
+        let new_page = match input {
+            "" => {
+                terms.clear();
+                None
+            }
+            "q" | "Q" => return Err("no selection made".into()),
+            "n" | "N" if page < pages => Some(page + 1),
+            "p" | "P" if page > 1 => Some(page - 1),
+            "n" | "N" => {
+                eprintln!("no more pages");
+                None
+            }
+            "p" | "P" => {
+                eprintln!("already on the first page");
+                None
             }
+            _ => match input.parse::<usize>() {
+                Ok(idx) if idx >= 1 && idx <= shown.len() => {
+                    let a = shown[idx - 1];
+                    eprintln!("Selected: {} (id {})", a.title, a.id);
+                    return Ok(a.id);
+                }
+                Ok(_) => {
+                    eprintln!("selection out of range");
+                    None
+                }
+                Err(_) => {
+                    terms.extend(input.split_whitespace().map(|t| t.to_lowercase()));
+                    None
+                }
+            },
+        };
 
-            eprintln!();
-            eprint!("Pick [1-{}]: ", resp.results.len());
-            io::stderr().flush().unwrap();
+        if let Some(p) = new_page {
+            let ps = p.to_string();
+            let resp: SearchResponse = api.get(
+                "search",
+                "/database/search",
+                &[
+                    ("q", name),
+                    ("type", "artist"),
+                    ("per_page", "10"),
+                    ("page", &ps),
+                ],
+            )?;
+            page = p;
+            pages = resp.pagination.pages.max(1);
+            results = resp.results;
+        }
+    }
+}
+
+/// Fetch picker details for one candidate. Failures leave fields empty
+/// rather than aborting the picker.
+fn fetch_candidate_info(api: &Discogs, artist_id: u64) -> CandidateInfo {
+    let detail: Option<ArtistDetail> = api
+        .get("artist-detail", &format!("/artists/{artist_id}"), &[])
+        .ok();
+
+    match detail {
+        Some(d) => CandidateInfo {
+            realname: d.realname.filter(|s| !s.is_empty()),
+            profile: strip_discogs_markup(&d.profile),
+            members: d.members.into_iter().map(|m| m.name).collect(),
+            groups: d.groups.into_iter().map(|g| g.name).collect(),
+        },
+        None => CandidateInfo {
+            realname: None,
+            profile: String::new(),
+            members: Vec::new(),
+            groups: Vec::new(),
+        },
+    }
+}
+
+/// Number of releases credited to a candidate, or `None` if the lookup
+/// failed.
+fn fetch_release_count(api: &Discogs, artist_id: u64) -> Option<u32> {
+    api.get::<ArtistReleasesPage>(
+        "artist-releases",
+        &format!("/artists/{artist_id}/releases"),
+        &[("per_page", "1")],
+    )
+    .ok()
+    .map(|r| r.pagination.items)
+}
+
+/// Reduce Discogs profile markup ("[a=Name]", "[l123]", "[b]...[/b]") to
+/// plain text on a single line. Other bracketed text is left alone.
+fn strip_discogs_markup(text: &str) -> String {
+    static NAMED: LazyLock<regex::Regex> =
+        LazyLock::new(|| regex::Regex::new(r"\[[almr]=([^\]]+)\]").unwrap());
+    static TAGS: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"(?i)\[(?:/?[bisu]|/?url(?:=[^\]]*)?|img=[^\]]*|[almr]\d+)\]").unwrap()
+    });
+    let text = NAMED.replace_all(text, "$1");
+    let text = TAGS.replace_all(&text, "");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// One "  N: Title (id X)" line per search result, for error messages.
+fn candidate_list(results: &[SearchHit]) -> String {
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, a)| format!("  {}: {} (id {})", i + 1, a.title, a.id))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ── remembered artist picks ────────────────────────────────────
+
+/// Artist name → ID choices saved in `<cache_dir>/aliases.yaml`, so an
+/// ambiguous name only has to be resolved by hand once.
+struct AliasMap {
+    path: String,
+    map: BTreeMap<String, u64>,
+}
+
+impl AliasMap {
+    fn load(cache_dir: &str) -> Self {
+        let path = format!("{cache_dir}/aliases.yaml");
+        let map = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_yml::from_str(&text).unwrap_or_else(|e| {
+                eprintln!("warning: ignoring unreadable {path}: {e}");
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self { path, map }
+    }
+
+    fn get(&self, name: &str) -> Option<u64> {
+        self.map.get(&name.to_lowercase()).copied()
+    }
+
+    /// Record a pick and save the file. Failure to save is only a warning.
+    fn remember(&mut self, name: &str, id: u64) {
+        self.map.insert(name.to_lowercase(), id);
+        let result = serde_yml::to_string(&self.map)
+            .map_err(|e| e.to_string())
+            .and_then(|yaml| save_file(&self.path, &yaml));
+        match result {
+            Ok(()) => eprintln!("  (remembered in {})", self.path),
+            Err(e) => eprintln!("warning: could not save {}: {e}", self.path),
+        }
+    }
+}
+
+// ── price history ──────────────────────────────────────────────
+
+/// One observed price, as a line of `<cache_dir>/prices.jsonl`.
+#[derive(Serialize, Deserialize, Clone)]
+struct PriceSnapshot {
+    /// "master" or "release"
+    kind: String,
+    id: u64,
+    /// Unix seconds
+    ts: u64,
+    currency: String,
+    lowest_price: Option<f64>,
+    num_for_sale: Option<u32>,
+}
+
+impl PriceSnapshot {
+    fn price(&self) -> Option<f64> {
+        self.lowest_price
+            .filter(|_| self.num_for_sale.unwrap_or(0) > 0)
+    }
+
+    /// "▲ +$2.00 since 2026-10-01" and the like, comparing this older
+    /// snapshot with the `current` lowest price.
+    fn change_to(&self, current: Option<f64>, currency: &str) -> String {
+        let since = date_str(self.ts);
+        match (self.price(), current) {
+            (Some(old), Some(new)) if (new - old).abs() < 0.005 => {
+                format!("= since {since}")
+            }
+            (Some(old), Some(new)) if new > old => {
+                format!("▲ +{} since {since}", money(new - old, currency))
+            }
+            (Some(old), Some(new)) => {
+                format!("▼ -{} since {since}", money(old - new, currency))
+            }
+            (Some(old), None) => format!("was {} on {since}", money(old, currency)),
+            (None, _) => format!("none for sale on {since}"),
+        }
+    }
+}
+
+/// Prices recorded by earlier runs. Appended to, never rewritten.
+struct PriceHistory {
+    path: String,
+    entries: Vec<PriceSnapshot>,
+}
+
+impl PriceHistory {
+    fn load(cache_dir: &str) -> Self {
+        let path = format!("{cache_dir}/prices.jsonl");
+        let mut entries = Vec::new();
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            for (n, line) in text.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(line) {
+                    Ok(snap) => entries.push(snap),
+                    Err(e) => eprintln!("warning: {path}:{}: skipping bad line: {e}", n + 1),
+                }
+            }
+        }
+        Self { path, entries }
+    }
 
-            let mut buf = String::new();
-            io::stdin()
-                .lock()
-                .read_line(&mut buf)
-                .map_err(|e| e.to_string())?;
+    /// Snapshots of one item, oldest first.
+    fn of(&self, kind: &str, id: u64) -> impl Iterator<Item = &PriceSnapshot> {
+        self.entries
+            .iter()
+            .filter(move |e| e.kind == kind && e.id == id)
+    }
 
-            let idx: usize = buf
-                .trim()
-                .parse()
-                .map_err(|_| "invalid number".to_string())?;
+    /// The latest earlier snapshot of a hit in the same currency.
+    fn last_seen(&self, r: &Info, currency: &str) -> Option<PriceSnapshot> {
+        self.of(r.kind, r.id)
+            .filter(|e| e.currency == currency)
+            .max_by_key(|e| e.ts)
+            .cloned()
+    }
 
-            if idx < 1 || idx > resp.results.len() {
-                return Err("selection out of range".into());
+    /// Append a snapshot of every item whose price was fetched this run.
+    /// Failure to save is only a warning.
+    fn record<'a>(&mut self, infos: impl IntoIterator<Item = &'a Info>, currency: &str) {
+        let ts = now_secs();
+        let mut seen = HashSet::new();
+        let mut lines = String::new();
+        for r in infos {
+            if r.market.num_for_sale.is_none() || !seen.insert((r.kind, r.id)) {
+                continue;
+            }
+            let snap = PriceSnapshot {
+                kind: r.kind.to_string(),
+                id: r.id,
+                ts,
+                currency: currency.to_string(),
+                lowest_price: r.market.lowest_price,
+                num_for_sale: r.market.num_for_sale,
+            };
+            if let Ok(line) = serde_json::to_string(&snap) {
+                lines.push_str(&line);
+                lines.push('\n');
             }
+            self.entries.push(snap);
+        }
+        if lines.is_empty() {
+            return;
+        }
+        if let Err(e) = append_file(&self.path, &lines) {
+            eprintln!("warning: could not save {}: {e}", self.path);
+        }
+    }
+}
+
+/// `prices history <id>`: every recorded price of one item with the
+/// change from the one before.
+fn price_history_report(
+    history: &PriceHistory,
+    id: u64,
+    kind: Option<ItemKind>,
+    output: OutputMode,
+) -> Result<(), String> {
+    let kinds: Vec<String> = match kind {
+        Some(k) => vec![format!("{k:?}").to_lowercase()],
+        None => vec!["master".into(), "release".into()],
+    };
+    let series: Vec<(&str, Vec<&PriceSnapshot>)> = kinds
+        .iter()
+        .map(|k| {
+            let mut snaps: Vec<_> = history.of(k, id).collect();
+            snaps.sort_by_key(|e| e.ts);
+            (k.as_str(), snaps)
+        })
+        .filter(|(_, snaps)| !snaps.is_empty())
+        .collect();
+    if series.is_empty() {
+        return Err(format!("no recorded prices for {id} in {}", history.path));
+    }
+
+    if output == OutputMode::Json {
+        let rows: Vec<_> = series
+            .iter()
+            .flat_map(|(_, snaps)| snaps)
+            .map(|e| {
+                let mut row = serde_json::to_value(e).unwrap_or_default();
+                row["date"] = date_str(e.ts).into();
+                row
+            })
+            .collect();
+        let text = serde_json::to_string_pretty(&rows).map_err(|e| format!("JSON output: {e}"))?;
+        println!("{text}");
+        return Ok(());
+    }
+
+    for (kind, snaps) in &series {
+        println!("Price history for {kind} {id}:");
+        let mut prev: Option<&PriceSnapshot> = None;
+        for e in snaps {
+            let price = match (e.num_for_sale, e.lowest_price) {
+                (Some(n), Some(p)) if n > 0 => format!("{} ({n} for sale)", money(p, &e.currency)),
+                _ => "none for sale".into(),
+            };
+            let change = prev
+                .filter(|p| p.currency == e.currency)
+                .map(|p| format!("  |  {}", p.change_to(e.price(), &e.currency)))
+                .unwrap_or_default();
+            println!("  {}  {price}{change}", date_str(e.ts));
+            prev = Some(e);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+// ── price-drop alerts ──────────────────────────────────────────
+
+/// A hit whose price fell below its threshold or dropped sharply since
+/// the last run.
+#[derive(Serialize)]
+struct PriceAlert {
+    /// "master" or "release"
+    kind: String,
+    id: u64,
+    title: String,
+    url: String,
+    currency: String,
+    price: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_price: Option<f64>,
+    /// Date of the previous observation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<String>,
+    /// e.g. "below $25.00" or "down 20% since 2026-10-01"
+    reasons: Vec<String>,
+    date: String,
+}
+
+/// Why a price of `price` now is alert-worthy against the last snapshot
+/// and the thresholds. A threshold only fires when the price crosses it,
+/// not on every run it stays below.
+fn alert_reasons(
+    price: f64,
+    prev: Option<&PriceSnapshot>,
+    below: Option<f64>,
+    drop_pct: Option<f64>,
+    currency: &str,
+) -> Vec<String> {
+    let prev_price = prev.and_then(PriceSnapshot::price);
+    let mut reasons = Vec::new();
+    if let Some(limit) = below
+        && price < limit
+        && !prev_price.is_some_and(|p| p < limit)
+    {
+        reasons.push(format!("below {}", money(limit, currency)));
+    }
+    if let (Some(pct), Some(old), Some(prev)) = (drop_pct, prev_price, prev)
+        && old > 0.0
+        && (old - price) / old * 100.0 >= pct
+    {
+        reasons.push(format!(
+            "down {:.0}% since {}",
+            (old - price) / old * 100.0,
+            date_str(prev.ts)
+        ));
+    }
+    reasons
+}
+
+/// Check every hit for sale against its thresholds: those in its wantlist
+/// note tags (any wanted version of a master counts), else the command
+/// line's. A hit listed under several artists is checked once.
+fn find_alerts(
+    selected: &[Selection],
+    history: &PriceHistory,
+    notes: &HashMap<u64, String>,
+    cli: &Cli,
+    currency: &str,
+) -> Vec<PriceAlert> {
+    let mut seen = HashSet::new();
+    let mut alerts = Vec::new();
+    for r in selected
+        .iter()
+        .flat_map(|(_, hits, _)| hits.iter().copied())
+    {
+        let Some(price) = r.market.price() else {
+            continue;
+        };
+        if !seen.insert((r.kind, r.id)) {
+            continue;
+        }
+        let tags: Vec<FilterTag> = r
+            .release_id
+            .iter()
+            .chain(&r.version_ids)
+            .filter_map(|id| notes.get(id))
+            .flat_map(|n| parse_note_tags(n))
+            .collect();
+        let below = tags.iter().find_map(|t| t.alert_below).or(cli.alert_below);
+        let drop_pct = tags.iter().find_map(|t| t.alert_drop).or(cli.alert_drop);
+
+        let prev = history.last_seen(r, currency);
+        let reasons = alert_reasons(price, prev.as_ref(), below, drop_pct, currency);
+        if reasons.is_empty() {
+            continue;
+        }
+        alerts.push(PriceAlert {
+            kind: r.kind.to_string(),
+            id: r.id,
+            title: r.title.clone(),
+            url: r.url.clone(),
+            currency: currency.to_string(),
+            price,
+            previous_price: prev.as_ref().and_then(PriceSnapshot::price),
+            since: prev.as_ref().map(|p| date_str(p.ts)),
+            reasons,
+            date: today_str(),
+        });
+    }
+    alerts
+}
 
-            let a = &resp.results[idx - 1];
-            eprintln!("Selected: {} (id {})", a.title, a.id);
-            Ok(a.id)
+/// Deliver alerts to each --alert-to target (stdout when none). With
+/// --output json, stdout alerts are part of the report instead. Delivery
+/// failures are warnings.
+fn send_alerts(alerts: &[PriceAlert], targets: &[String], output: OutputMode) {
+    let stdout = ["stdout".to_string()];
+    let targets = if targets.is_empty() {
+        &stdout[..]
+    } else {
+        targets
+    };
+    eprintln!("{} price alert(s)", alerts.len());
+    for target in targets {
+        if target == "stdout" || target == "-" {
+            if output == OutputMode::Json {
+                continue;
+            }
+            println!();
+            for a in alerts {
+                println!(
+                    "ALERT  {} — {} ({})",
+                    a.title,
+                    money(a.price, &a.currency),
+                    a.reasons.join(", ")
+                );
+                println!("       {}", a.url);
+            }
+        } else if target.starts_with("http://") || target.starts_with("https://") {
+            for a in alerts {
+                if let Err(e) = post_webhook(target, a) {
+                    eprintln!("warning: alert webhook {target}: {e}");
+                }
+            }
+        } else {
+            let mut lines = String::new();
+            for a in alerts {
+                if let Ok(line) = serde_json::to_string(a) {
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+            }
+            if let Err(e) = append_file(&expand_home(target), &lines) {
+                eprintln!("warning: could not write alerts to {target}: {e}");
+            }
         }
     }
 }
 
+/// POST one alert as JSON.
+fn post_webhook(url: &str, alert: &PriceAlert) -> Result<(), String> {
+    ureq::post(url)
+        .set("User-Agent", USER_AGENT)
+        .timeout(Duration::from_secs(15))
+        .send_json(alert)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Append `contents` to `path`, creating it and its parent directories as
+/// needed.
+fn append_file(path: &str, contents: &str) -> Result<(), String> {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+/// Write `contents` to `path`, creating parent directories as needed.
+fn save_file(path: &str, contents: &str) -> Result<(), String> {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, contents).map_err(|e| e.to_string())
+}
+
 // ── paginated fetchers ─────────────────────────────────────────
 
 fn fetch_artist_releases(api: &Discogs, artist_id: u64) -> Result<Vec<ArtistRelease>, String> {
@@ -1362,90 +5591,88 @@ fn fetch_artist_releases(api: &Discogs, artist_id: u64) -> Result<Vec<ArtistRele
 fn fetch_master_info(
     api: &Discogs,
     master_id: u64,
-    need_price: bool,
+    filters: &Filters,
     need_detail: bool,
-    price_limit: Option<f64>,
-    has: &HashSet<String>,
-    not: &HashSet<String>,
-    only: &HashSet<String>,
-    ignore: &HashSet<String>,
+    need_community: bool,
 ) -> Result<FetchedInfo, String> {
-    let have_filters = !has.is_empty() || !not.is_empty() || !only.is_empty();
-
     // ── Step 1: Check formats FIRST (cheap — avoids master-detail for failures) ──
-    let formats = if !not.is_empty() || !only.is_empty() {
-        master_formats_full_early_exit(api, master_id, not, only, ignore)?
-    } else {
-        master_formats_full(api, master_id)?
-    };
+    let early_exit = !filters.not.is_empty() || !filters.only.is_empty();
+    let scan = scan_master_versions(api, master_id, filters, early_exit)?;
+    let version_filtered = filters.have_version_filters();
 
     // Quick check: will this release pass the format filter?
-    if have_filters {
-        let dominated = {
-            let lc: HashSet<String> = formats
-                .iter()
-                .map(|f| f.to_lowercase())
-                .filter(|f| !ignore.contains(f))
-                .collect();
-            let has_ok = has.iter().all(|h| lc.contains(h));
-            let not_ok = !not.iter().any(|n| lc.contains(n));
-            let only_ok =
-                only.is_empty() || (!lc.is_empty() && lc.iter().all(|f| only.contains(f)));
-            !(has_ok && not_ok && only_ok)
-        };
-        if dominated {
-            return Ok(FetchedInfo {
-                formats,
-                lowest_price: None,
-                num_for_sale: None,
-                artists: Vec::new(),
-                release_id: None,
-            });
-        }
+    let rejection = scan.rejection(filters);
+    let fetched = FetchedInfo {
+        formats: scan.formats,
+        descriptions: None,
+        countries: scan.countries,
+        label: scan.label,
+        catno: scan.catno,
+        genres: Vec::new(),
+        styles: Vec::new(),
+        community: None,
+        lowest_price: None,
+        num_for_sale: None,
+        artists: Vec::new(),
+        release_id: None,
+        version_ids: scan.version_ids,
+        rejection,
+    };
+    if fetched.rejection.is_some() {
+        return Ok(fetched);
     }
 
     // ── Step 2: Format filter passed — fetch master-detail for price/artists/main_release ──
-    let (lowest_price, num_for_sale, artists, main_release) = if need_price || need_detail {
-        let detail = fetch_master_detail(api, master_id)?;
-        if let Some(limit) = price_limit {
-            let too_expensive = match (detail.num_for_sale, detail.lowest_price) {
-                (Some(n), Some(p)) if n > 0 => p > limit,
-                _ => true,
-            };
-            if too_expensive {
-                api.stats.borrow_mut().skipped_price += 1;
-                return Ok(FetchedInfo {
-                    formats,
-                    lowest_price: detail.lowest_price,
-                    num_for_sale: detail.num_for_sale,
-                    artists: detail.artists,
-                    release_id: detail.main_release,
-                });
-            }
+    // With version-level filters the main release may be a pressing that
+    // was filtered out, so stand in the preferred passing version instead.
+    let preferred = scan.preferred.filter(|_| version_filtered);
+    if !need_detail {
+        return Ok(FetchedInfo {
+            release_id: preferred,
+            ..fetched
+        });
+    }
+    let mut detail = fetch_master_detail(api, master_id)?;
+    let priced = price_preferred_version(api, &mut detail, preferred)?;
+    let rejection = first_failure(master_detail_verdicts(filters, &detail, &api.currency));
+    if rejection
+        .as_ref()
+        .is_some_and(|(stage, _)| *stage == Stage::PriceSkip)
+    {
+        api.stats.borrow_mut().skipped_price += 1;
+    }
+    let release_id = preferred.or(detail.main_release);
+    let community = match (detail.community, priced, release_id) {
+        (Some(c), _, _) => Some(c),
+        (None, Some(r), _) => r.community,
+        (None, None, Some(rid)) if need_community && rejection.is_none() => {
+            fetch_release_community(api, rid)?
         }
-        (
-            detail.lowest_price,
-            detail.num_for_sale,
-            detail.artists,
-            detail.main_release,
-        )
-    } else {
-        (None, None, Vec::new(), None)
+        _ => None,
     };
-
+    let rejection =
+        rejection.or_else(|| first_failure(community_verdict(filters, community.as_ref())));
     Ok(FetchedInfo {
-        formats,
-        lowest_price,
-        num_for_sale,
-        artists,
-        release_id: main_release,
+        genres: detail.genres,
+        styles: detail.styles,
+        community,
+        lowest_price: detail.lowest_price,
+        num_for_sale: detail.num_for_sale,
+        artists: detail.artists,
+        release_id,
+        rejection,
+        ..fetched
     })
 }
 
-/// Fetch all major_formats across all versions of a master (no server-side filter).
-/// Used when no format filters are specified.
-fn master_formats_full(api: &Discogs, master_id: u64) -> Result<BTreeSet<String>, String> {
+/// Fetch all major_formats across all versions of a master, ignoring every
+/// filter. Used by --verify-search. Also returns the version IDs.
+fn master_formats_full(
+    api: &Discogs,
+    master_id: u64,
+) -> Result<(BTreeSet<String>, Vec<u64>), String> {
     let mut fmts = BTreeSet::new();
+    let mut ids = Vec::new();
     let mut page = 1u32;
 
     loop {
@@ -1458,6 +5685,7 @@ fn master_formats_full(api: &Discogs, master_id: u64) -> Result<BTreeSet<String>
         )?;
 
         for v in &resp.versions {
+            ids.push(v.id);
             if let Some(mf) = &v.major_formats {
                 fmts.extend(mf.iter().cloned());
             }
@@ -1466,59 +5694,176 @@ fn master_formats_full(api: &Discogs, master_id: u64) -> Result<BTreeSet<String>
         if page >= resp.pagination.pages {
             break;
         }
-        page += 1;
+        page += 1;
+    }
+
+    Ok((fmts, ids))
+}
+
+/// What paging through a master's versions found.
+#[derive(Default)]
+struct VersionScan {
+    /// Whether paging stopped at the first disqualifying format
+    early_exit: bool,
+    /// Major formats of the versions that passed the version-level filters
+    formats: BTreeSet<String>,
+    /// Every version ID, filtered or not (for owned/wanted exclusion)
+    version_ids: Vec<u64>,
+    /// Countries of the versions that passed
+    countries: BTreeSet<String>,
+    /// How many versions the version-level filters dropped, and why the
+    /// first of them was dropped
+    skipped: usize,
+    first_skip: Option<String>,
+    /// First passing version whose own formats pass, else the first
+    /// passing version; `None` when no version passed
+    preferred: Option<u64>,
+    /// Label and catalog number of the preferred version
+    label: Option<String>,
+    catno: Option<String>,
+    /// The disqualifying format that stopped paging early, if any
+    offending: Option<String>,
+}
+
+impl VersionScan {
+    /// Verdicts on the walk, in pipeline order: early exit, version-level
+    /// filters, then the format filter over the counted formats.
+    fn verdicts(&self, filters: &Filters) -> Vec<StageVerdict> {
+        let total = self.version_ids.len();
+        let mut verdicts = Vec::new();
+        if self.early_exit {
+            verdicts.push(StageVerdict::new(
+                Stage::EarlyExit,
+                self.offending.clone(),
+                format!("{total} versions, none disqualifying"),
+            ));
+        }
+        if filters.have_version_filters() {
+            let failure = self.preferred.is_none().then(|| {
+                format!(
+                    "none of {} versions pass ({})",
+                    self.skipped,
+                    self.first_skip.clone().unwrap_or_default()
+                )
+            });
+            verdicts.push(StageVerdict::new(
+                Stage::VersionFilter,
+                failure,
+                format!(
+                    "{} of {total} versions pass the pressing filters",
+                    total - self.skipped
+                ),
+            ));
+        }
+        let listed: Vec<&str> = self.formats.iter().map(String::as_str).collect();
+        verdicts.push(StageVerdict::new(
+            Stage::FormatFilter,
+            filters.format_rejection(&self.formats),
+            format!("formats: {}", listed.join(", ")),
+        ));
+        verdicts
     }
 
-    Ok(fmts)
+    /// The stage and reason the run drops the master for after the walk.
+    fn rejection(&self, filters: &Filters) -> Option<(Stage, String)> {
+        first_failure(self.verdicts(filters))
+    }
 }
 
-/// Fetch formats with early termination on excluded format (for --only mode).
-/// Fetch formats with early termination.
-/// Stops as soon as a disqualifying format is found:
+/// Page through a master's versions, collecting formats from the versions
+/// that pass the version-level filters (country, label).
+///
+/// With `early_exit`, stops as soon as a disqualifying format is found:
 ///   - for --not: any format in `excludes`
 ///   - for --only: any format NOT in `only ∪ ignore`
-fn master_formats_full_early_exit(
+fn scan_master_versions(
     api: &Discogs,
     master_id: u64,
-    excludes: &HashSet<String>,
-    only: &HashSet<String>,
-    ignore: &HashSet<String>,
-) -> Result<BTreeSet<String>, String> {
-    let mut fmts = BTreeSet::new();
-    let mut page = 1u32;
-
-    loop {
+    filters: &Filters,
+    early_exit: bool,
+) -> Result<VersionScan, String> {
+    let path = format!("/masters/{master_id}/versions");
+    let scan = scan_versions(filters, early_exit, |page| {
         let p = page.to_string();
-        let path = format!("/masters/{master_id}/versions");
-        let resp: MasterVersionsPage = api.get(
+        api.get(
             "master-versions",
             &path,
             &[("page", &p), ("per_page", "100")],
-        )?;
+        )
+    })?;
+    if scan.offending.is_some() {
+        api.stats.borrow_mut().skipped_early_exit += 1;
+    }
+    Ok(scan)
+}
+
+/// The walk behind `scan_master_versions`, over pages from `fetch_page`
+/// (numbered from 1).
+fn scan_versions(
+    filters: &Filters,
+    early_exit: bool,
+    mut fetch_page: impl FnMut(u32) -> Result<MasterVersionsPage, String>,
+) -> Result<VersionScan, String> {
+    let (excludes, only, ignore) = (&filters.not, &filters.only, &filters.ignore);
+    let mut scan = VersionScan {
+        early_exit,
+        ..VersionScan::default()
+    };
+    // (id, label, catno) of the first passing version, and of the first
+    // whose own formats pass
+    let mut first_passing = None;
+    let mut first_good = None;
+    let mut page = 1u32;
 
+    loop {
+        let resp = fetch_page(page)?;
         for v in &resp.versions {
-            if let Some(mf) = &v.major_formats {
-                fmts.extend(mf.iter().cloned());
+            scan.version_ids.push(v.id);
+            let pressing = Pressing::of_version(v);
+            if let Some(why) = filters.pressing_rejection(&pressing) {
+                // With --flagged-versions count, a bootleg's formats still
+                // count against the master, as long as it's only flagged
+                let only_flagged = filters
+                    .flag_rejection(pressing.descriptions.iter().copied())
+                    .is_some_and(|f| f == why);
+                if only_flagged && filters.flagged_policy == FlaggedPolicy::Count {
+                    scan.formats
+                        .extend(v.major_formats.iter().flatten().cloned());
+                }
+                scan.skipped += 1;
+                scan.first_skip
+                    .get_or_insert_with(|| format!("version {}: {why}", v.id));
+                continue;
             }
+            scan.countries.extend(pressing.country.map(String::from));
+            let summary = || (v.id, v.label.clone(), v.catno.clone());
+            first_passing.get_or_insert_with(summary);
+            let fmts = v.major_formats.iter().flatten();
+            if first_good.is_none() && filters.formats_ok(fmts.clone()) {
+                first_good = Some(summary());
+            }
+            scan.formats.extend(fmts.cloned());
         }
 
-        // Check --not: any explicitly excluded format found?
-        let not_fail = !excludes.is_empty()
-            && fmts.iter().any(|f| {
+        if early_exit {
+            // Check --not: any explicitly excluded format found?
+            let not_fail = scan.formats.iter().find(|f| {
                 let lc = f.to_lowercase();
                 !ignore.contains(&lc) && excludes.contains(&lc)
             });
 
-        // Check --only: any format outside the allowed set?
-        let only_fail = !only.is_empty()
-            && fmts.iter().any(|f| {
+            // Check --only: any format outside the allowed set?
+            let only_fail = scan.formats.iter().find(|f| {
                 let lc = f.to_lowercase();
-                !ignore.contains(&lc) && !only.contains(&lc)
+                !only.is_empty() && !ignore.contains(&lc) && !only.contains(&lc)
             });
 
-        if not_fail || only_fail {
-            api.stats.borrow_mut().skipped_early_exit += 1;
-            break;
+            scan.offending = not_fail
+                .map(|f| format!("{} (--not)", f.to_lowercase()))
+                .or_else(|| only_fail.map(|f| format!("{} (outside --only)", f.to_lowercase())));
+            if scan.offending.is_some() {
+                break;
+            }
         }
 
         if page >= resp.pagination.pages {
@@ -1527,7 +5872,12 @@ fn master_formats_full_early_exit(
         page += 1;
     }
 
-    Ok(fmts)
+    if let Some((id, label, catno)) = first_good.or(first_passing) {
+        scan.preferred = Some(id);
+        scan.label = label.filter(|l| !l.is_empty());
+        scan.catno = catno.filter(|c| !c.is_empty());
+    }
+    Ok(scan)
 }
 
 /// Bulk-search for master releases by an artist that have a given format.
@@ -1568,48 +5918,160 @@ fn search_masters_with_format(
     Ok(ids)
 }
 
-/// Capitalize first letter of a format name for the API.
-fn title_case(s: &str) -> String {
-    let mut c = s.chars();
-    match c.next() {
-        None => String::new(),
-        Some(first) => {
-            let mut out = first.to_uppercase().to_string();
-            out.extend(c);
-            out
+/// --verify-search: re-check search-excluded masters via /versions.
+///
+/// Search results come from an index that can lag behind edits, so a
+/// master may be excluded for a format none of its versions have any more.
+/// Checks every exclusion (`sample == 0`) or an evenly spaced sample,
+/// reports the false ones and lets them through to the individual checks.
+/// When the false rate exceeds `threshold` percent, drops all search
+/// exclusions for this artist.
+fn verify_search_exclusions(
+    api: &Discogs,
+    masters: &[&DedupRelease],
+    search_excluded: &mut HashMap<u64, String>,
+    sample: usize,
+    threshold: f64,
+) {
+    let mut ids: Vec<u64> = search_excluded.keys().copied().collect();
+    ids.sort();
+    if sample > 0 && sample < ids.len() {
+        let step = ids.len() as f64 / sample as f64;
+        ids = (0..sample)
+            .map(|i| ids[(i as f64 * step) as usize])
+            .collect();
+    }
+
+    eprintln!("Verifying {} search exclusions...", ids.len());
+    let title_of = |id: u64| {
+        masters
+            .iter()
+            .find(|m| m.id == id)
+            .map_or(String::new(), |m| m.title.clone())
+    };
+    let mut checked = 0usize;
+    let mut wrong: Vec<u64> = Vec::new();
+    for (i, &id) in ids.iter().enumerate() {
+        eprint!(
+            "\r  [{}/{}] {}\x1b[K",
+            i + 1,
+            ids.len(),
+            trunc(&title_of(id), 50)
+        );
+        let claimed = search_excluded[&id].to_lowercase();
+        match master_formats_full(api, id) {
+            Ok((formats, _)) => {
+                checked += 1;
+                if !formats.iter().any(|f| f.to_lowercase() == claimed) {
+                    wrong.push(id);
+                }
+            }
+            Err(e) => eprintln!("\r  warning: could not verify master {id}: {e}\x1b[K"),
+        }
+    }
+    eprint!("\r\x1b[K");
+
+    {
+        let mut stats = api.stats.borrow_mut();
+        stats.search_verified += checked as u32;
+        stats.search_false += wrong.len() as u32;
+    }
+    for id in &wrong {
+        eprintln!(
+            "  false exclusion: {} (master {id}) has no {} version",
+            title_of(*id),
+            search_excluded[id]
+        );
+    }
+
+    let rate = if checked > 0 {
+        100.0 * wrong.len() as f64 / checked as f64
+    } else {
+        0.0
+    };
+    eprintln!(
+        "  Search verified: {}/{checked} false exclusions ({rate:.1}%)",
+        wrong.len()
+    );
+    if rate > threshold {
+        eprintln!(
+            "  Over the {threshold}% threshold; checking all {} masters individually",
+            search_excluded.len()
+        );
+        search_excluded.clear();
+    } else {
+        for id in &wrong {
+            search_excluded.remove(id);
         }
     }
 }
 
 fn fetch_master_detail(api: &Discogs, master_id: u64) -> Result<MasterDetail, String> {
     let path = format!("/masters/{master_id}");
-    api.get("master-detail", &path, &[])
+    api.get("master-detail", &path, &[("curr_abbr", &api.currency)])
 }
 
-/// Returns (formats, lowest_price, num_for_sale, artists)
-fn release_info(
+/// Under version-level filters a master's marketplace price covers every
+/// pressing, so take the price and for-sale count from the `preferred`
+/// passing version instead. Returns that version's detail.
+fn price_preferred_version(
     api: &Discogs,
-    release_id: u64,
-) -> Result<
-    (
-        BTreeSet<String>,
-        Option<f64>,
-        Option<u32>,
-        Vec<ArtistCredit>,
-    ),
-    String,
-> {
+    detail: &mut MasterDetail,
+    preferred: Option<u64>,
+) -> Result<Option<ReleaseDetail>, String> {
+    let Some(release_id) = preferred else {
+        return Ok(None);
+    };
+    let path = format!("/releases/{release_id}");
+    let release: ReleaseDetail =
+        api.get("release-detail", &path, &[("curr_abbr", &api.currency)])?;
+    detail.lowest_price = release.lowest_price;
+    detail.num_for_sale = release.num_for_sale;
+    Ok(Some(release))
+}
+
+/// Community stats of the release standing in for a master (its main
+/// release, or the preferred version under version-level filters).
+fn fetch_release_community(api: &Discogs, release_id: u64) -> Result<Option<Community>, String> {
+    let path = format!("/releases/{release_id}");
+    let resp: ReleaseDetail = api.get("release-detail", &path, &[("curr_abbr", &api.currency)])?;
+    Ok(resp.community)
+}
+
+/// Fetch formats, descriptions, price and artists for a single release.
+fn release_info(api: &Discogs, release_id: u64, filters: &Filters) -> Result<FetchedInfo, String> {
     let path = format!("/releases/{release_id}");
-    let resp: ReleaseDetail = api.get("release-detail", &path, &[])?;
+    let resp: ReleaseDetail = api.get("release-detail", &path, &[("curr_abbr", &api.currency)])?;
+    let rejection = first_failure(release_detail_verdicts(filters, &resp));
+    let first_label = resp.labels.first();
+    let label = first_label.map(|l| l.name.clone());
+    let catno = first_label
+        .map(|l| l.catno.clone())
+        .filter(|c| !c.is_empty());
 
     let mut fmts = BTreeSet::new();
-    if let Some(entries) = resp.formats {
-        for e in entries {
-            fmts.insert(e.name);
-        }
+    let mut descriptions = BTreeSet::new();
+    for e in resp.formats.into_iter().flatten() {
+        fmts.insert(e.name);
+        descriptions.extend(e.descriptions);
     }
 
-    Ok((fmts, resp.lowest_price, resp.num_for_sale, resp.artists))
+    Ok(FetchedInfo {
+        formats: fmts,
+        descriptions: Some(descriptions),
+        countries: resp.country.into_iter().collect(),
+        label,
+        catno,
+        genres: resp.genres,
+        styles: resp.styles,
+        community: resp.community,
+        lowest_price: resp.lowest_price,
+        num_for_sale: resp.num_for_sale,
+        artists: resp.artists,
+        release_id: Some(release_id),
+        version_ids: Vec::new(),
+        rejection,
+    })
 }
 
 /// Check if an error message indicates a transient/retryable error.
@@ -1630,54 +6092,6 @@ fn is_transient(err: &str) -> bool {
         || err.contains("Connection reset")
 }
 
-/// Parse the inline format string from the artist-releases endpoint.
-///
-/// The format string looks like "CD, Album" or "Vinyl, 12\", 45 RPM" or
-/// "2×File, FLAC, Album". We extract known major format names (Vinyl, CD,
-/// File, Cassette, DVD, Blu-ray, Box Set) from the comma-separated segments.
-fn parse_inline_formats(fmt_str: &str) -> BTreeSet<String> {
-    // Known major format names that Discogs uses
-    const KNOWN_FORMATS: &[&str] = &[
-        "vinyl",
-        "cd",
-        "file",
-        "cassette",
-        "dvd",
-        "blu-ray",
-        "box set",
-        "shellac",
-        "flexi-disc",
-        "lathe cut",
-    ];
-
-    let mut found = BTreeSet::new();
-    for segment in fmt_str.split(',') {
-        let seg = segment.trim();
-        // Handle quantity prefixes like "2×CD" or "2xFile"
-        let cleaned = seg
-            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '×' || c == 'x' || c == 'X')
-            .trim();
-        let lower = cleaned.to_lowercase();
-        for &known in KNOWN_FORMATS {
-            if lower == known {
-                // Capitalize the first letter for canonical display
-                let mut c = known.chars();
-                let canonical: String = match c.next() {
-                    None => continue,
-                    Some(first) => {
-                        let mut s = first.to_uppercase().to_string();
-                        s.extend(c);
-                        s
-                    }
-                };
-                found.insert(canonical);
-                break;
-            }
-        }
-    }
-    found
-}
-
 // ── wantlist support ───────────────────────────────────────────
 
 fn fetch_identity(api: &Discogs) -> Result<String, String> {
@@ -1710,49 +6124,246 @@ fn fetch_wantlist_notes(api: &Discogs, username: &str) -> Result<HashMap<u64, St
     Ok(map)
 }
 
+/// Fetch all release IDs in the user's collection (folder 0 = "All").
+fn fetch_collection_ids(api: &Discogs, username: &str) -> Result<HashSet<u64>, String> {
+    let mut ids = HashSet::new();
+    let mut page = 1u32;
+
+    loop {
+        let p = page.to_string();
+        let path = format!("/users/{username}/collection/folders/0/releases");
+        let resp: CollectionPage =
+            api.get("collection", &path, &[("page", &p), ("per_page", "100")])?;
+
+        ids.extend(resp.releases.iter().map(|r| r.id));
+
+        if resp.pagination.pages == 0 || page >= resp.pagination.pages {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(ids)
+}
+
+/// Resolve a collection folder given by name (case-insensitive) or numeric ID.
+fn resolve_collection_folder(api: &Discogs, username: &str, folder: &str) -> Result<u64, String> {
+    let path = format!("/users/{username}/collection/folders");
+    let resp: CollectionFolders = api.get("collection-folders", &path, &[])?;
+
+    let found = resp
+        .folders
+        .iter()
+        .find(|f| f.id.to_string() == folder || f.name.eq_ignore_ascii_case(folder));
+
+    match found {
+        // Folder 0 ("All") is a virtual view and can't be added to
+        Some(f) if f.id == 0 => Err(format!(
+            "cannot add to folder \"{}\"; pick a real folder (e.g. \"Uncategorized\")",
+            f.name
+        )),
+        Some(f) => Ok(f.id),
+        None => {
+            let names: Vec<_> = resp.folders.iter().map(|f| f.name.as_str()).collect();
+            Err(format!(
+                "no collection folder named \"{folder}\" (available: {})",
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+/// Find the collection field used for notes: a textarea named "Notes",
+/// falling back to the first textarea field.
+fn resolve_notes_field(api: &Discogs, username: &str) -> Result<u64, String> {
+    let path = format!("/users/{username}/collection/fields");
+    let resp: CollectionFields = api.get("collection-fields", &path, &[])?;
+
+    let textareas: Vec<_> = resp
+        .fields
+        .iter()
+        .filter(|f| f.kind == "textarea")
+        .collect();
+    textareas
+        .iter()
+        .find(|f| f.name.eq_ignore_ascii_case("notes"))
+        .or(textareas.first())
+        .map(|f| f.id)
+        .ok_or_else(|| "collection has no textarea field to store notes in".into())
+}
+
+/// Ensure a release is in the given collection folder and tag its notes field.
+/// Reuses an existing instance in that folder rather than adding a duplicate.
+/// Returns true if a new instance was added, false if an existing one was updated.
+fn add_to_collection_folder(
+    api: &Discogs,
+    username: &str,
+    folder_id: u64,
+    field_id: u64,
+    release_id: u64,
+    tag: &FilterTag,
+) -> Result<bool, String> {
+    let path = format!("/users/{username}/collection/releases/{release_id}");
+    let instances = match api.get::<CollectionInstances>("collection-release", &path, &[]) {
+        Ok(resp) => resp.releases,
+        // Not in the collection at all
+        Err(e) if e.contains("404") => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let existing = instances.iter().find(|i| i.folder_id == folder_id);
+    let folder_path =
+        format!("/users/{username}/collection/folders/{folder_id}/releases/{release_id}");
+
+    let (instance_id, old_notes, added) = match existing {
+        Some(inst) => {
+            let notes = inst
+                .notes
+                .iter()
+                .find(|n| n.field_id == field_id)
+                .map(|n| n.value.clone())
+                .unwrap_or_default();
+            (inst.instance_id, notes, false)
+        }
+        None => {
+            let body = api.request(
+                "POST",
+                "collection-add",
+                &folder_path,
+                &serde_json::json!({}),
+            )?;
+            let resp: CollectionAdded =
+                serde_json::from_str(&body).map_err(|e| format!("JSON parse: {e}"))?;
+            (resp.instance_id, String::new(), true)
+        }
+    };
+
+    let new_notes = update_notes(&old_notes, tag);
+    let field_path = format!("{folder_path}/instances/{instance_id}/fields/{field_id}");
+    let notes_body = serde_json::json!({ "value": new_notes });
+    api.request("POST", "collection-field", &field_path, &notes_body)?;
+
+    Ok(added)
+}
+
 /// Build the query summary string from filter args.
 /// e.g. "has:vinyl not:cd,file <$50"
-fn build_query_summary(
-    has: &HashSet<String>,
-    not: &HashSet<String>,
-    only: &HashSet<String>,
-    ignore: &HashSet<String>,
-    price_limit: Option<f64>,
-) -> String {
+fn build_query_summary(filters: &Filters, currency: &str) -> String {
     let mut parts = Vec::new();
-    if !only.is_empty() {
-        let mut v: Vec<_> = only.iter().map(String::as_str).collect();
-        v.sort();
-        parts.push(format!("only:{}", v.join(",")));
+    let lists = [
+        ("only", &filters.only),
+        ("has", &filters.has),
+        ("not", &filters.not),
+        ("ignore", &filters.ignore),
+        ("desc", &filters.desc),
+        ("not-desc", &filters.not_desc),
+        ("country", &filters.country),
+        ("not-country", &filters.not_country),
+        ("genre", &filters.genre),
+        ("not-genre", &filters.not_genre),
+        ("style", &filters.style),
+        ("not-style", &filters.not_style),
+    ];
+    for (key, set) in lists {
+        if !set.is_empty() {
+            let mut v: Vec<_> = set.iter().map(String::as_str).collect();
+            v.sort();
+            parts.push(format!("{key}:{}", v.join(",")));
+        }
+    }
+    for (key, pats) in [
+        ("label", &filters.label_match),
+        ("not-label", &filters.not_label),
+    ] {
+        if !pats.is_empty() {
+            let v: Vec<String> = pats.iter().map(LabelPattern::to_string).collect();
+            parts.push(format!("{key}:{}", v.join(",")));
+        }
+    }
+    let list = &filters.list;
+    if list.year_from.is_some() || list.year_to.is_some() {
+        let year = |y: Option<u32>| y.map(|y| y.to_string()).unwrap_or_default();
+        parts.push(format!(
+            "years:{}-{}",
+            year(list.year_from),
+            year(list.year_to)
+        ));
+    }
+    if let Some(re) = &list.title_match {
+        parts.push(format!("title:{}", re.as_str()));
     }
-    if !has.is_empty() {
-        let mut v: Vec<_> = has.iter().map(String::as_str).collect();
-        v.sort();
-        parts.push(format!("has:{}", v.join(",")));
+    if let Some(re) = &list.title_exclude {
+        parts.push(format!("not-title:{}", re.as_str()));
     }
-    if !not.is_empty() {
-        let mut v: Vec<_> = not.iter().map(String::as_str).collect();
-        v.sort();
-        parts.push(format!("not:{}", v.join(",")));
+    if let Some(kind) = list.kind {
+        parts.push(format!("{kind}s-only"));
     }
-    if !ignore.is_empty() {
-        let mut v: Vec<_> = ignore.iter().map(String::as_str).collect();
-        v.sort();
-        parts.push(format!("ignore:{}", v.join(",")));
+    let mut flags: Vec<&str> = filters
+        .flagged
+        .iter()
+        .map(|&(_, flag)| &flag[2..])
+        .collect();
+    flags.dedup();
+    parts.extend(flags.into_iter().map(String::from));
+    if let Some(n) = filters.min_have {
+        parts.push(format!("have:{n}+"));
+    }
+    if let Some(n) = filters.min_want {
+        parts.push(format!("want:{n}+"));
+    }
+    if let Some(r) = filters.min_rating {
+        parts.push(format!("rating:{r}+"));
     }
-    if let Some(limit) = price_limit {
-        parts.push(format!("<${:.0}", limit));
+    if let Some(r) = filters.min_scarcity {
+        parts.push(format!("scarcity:{r}+"));
+    }
+    if let Some(limit) = filters.price_limit {
+        // USD keeps the original "<$50" spelling so existing tags still match
+        if currency == "USD" {
+            parts.push(format!("<${:.0}", limit));
+        } else {
+            parts.push(format!("<{:.0} {currency}", limit));
+        }
     }
     parts.join(" ")
 }
 
+fn tag_block_regex() -> &'static regex::Regex {
+    static RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"(?s)\[format-filter\]\s*\n?(.*?)\n?\s*\[/format-filter\]").unwrap()
+    });
+    &RE
+}
+
+/// All tag entries from every [format-filter] block in a note.
+fn parse_note_tags(notes: &str) -> Vec<FilterTag> {
+    let mut tags: Vec<FilterTag> = Vec::new();
+    for cap in tag_block_regex().captures_iter(notes) {
+        if let Some(yaml_str) = cap.get(1)
+            && let Ok(parsed) = serde_yml::from_str::<Vec<FilterTag>>(yaml_str.as_str())
+        {
+            tags.extend(parsed);
+        }
+    }
+    tags
+}
+
 /// Today's date as YYYY-MM-DD using system time.
 fn today_str() -> String {
+    date_str(now_secs())
+}
+
+/// Seconds since the Unix epoch.
+fn now_secs() -> u64 {
     use std::time::SystemTime;
-    let dur = SystemTime::now()
+    SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap();
-    let secs = dur.as_secs();
+        .unwrap()
+        .as_secs()
+}
+
+/// A Unix timestamp as YYYY-MM-DD (UTC).
+fn date_str(secs: u64) -> String {
     // Simple date calculation (good enough — no timezone crate needed)
     let days = secs / 86400;
     let (y, m, d) = days_to_ymd(days);
@@ -1780,28 +6391,27 @@ const TAG_CLOSE: &str = "[/format-filter]";
 /// Update wantlist notes: extract all [format-filter] blocks, coalesce,
 /// add/update the current tag, reassemble with one block at the end.
 fn update_notes(existing_notes: &str, new_tag: &FilterTag) -> String {
-    let re =
-        regex::Regex::new(r"(?s)\[format-filter\]\s*\n?(.*?)\n?\s*\[/format-filter\]").unwrap();
-
-    // Extract all tag lines from all blocks
-    let mut tags: Vec<FilterTag> = Vec::new();
-    for cap in re.captures_iter(existing_notes) {
-        if let Some(yaml_str) = cap.get(1) {
-            if let Ok(parsed) = serde_yml::from_str::<Vec<FilterTag>>(yaml_str.as_str()) {
-                tags.extend(parsed);
-            }
-        }
-    }
+    let re = tag_block_regex();
+    let mut tags = parse_note_tags(existing_notes);
 
     // Strip all blocks from user text
     let user_text = re.replace_all(existing_notes, "").to_string();
     let user_text = user_text.trim().to_string();
 
-    // Remove any existing entry that matches on query+artist (ignoring date)
-    tags.retain(|t| !(t.query == new_tag.query && t.artist == new_tag.artist));
+    // Remove any existing entry that matches on query+artist (ignoring
+    // date), keeping alert thresholds the new tag doesn't set
+    let mut new_tag = new_tag.clone();
+    tags.retain(|t| {
+        let same = t.query == new_tag.query && t.artist == new_tag.artist;
+        if same {
+            new_tag.alert_below = new_tag.alert_below.or(t.alert_below);
+            new_tag.alert_drop = new_tag.alert_drop.or(t.alert_drop);
+        }
+        !same
+    });
 
     // Append new tag at end
-    tags.push(new_tag.clone());
+    tags.push(new_tag);
 
     // Serialize tags back to YAML
     let yaml = serde_yml::to_string(&tags).unwrap_or_default();
@@ -1817,6 +6427,64 @@ fn update_notes(existing_notes: &str, new_tag: &FilterTag) -> String {
 
 // ── utilities ──────────────────────────────────────────────────
 
+/// Print the matching releases as a single JSON document on stdout.
+/// A batch run nests one entry per artist under "artists"; --explain adds
+/// a "rejected" array per artist.
+fn print_json_report(
+    selected: &[Selection],
+    query: &str,
+    currency: &str,
+    batch: bool,
+    explain: bool,
+    shopping: Option<&ShoppingList>,
+    alerts: &[PriceAlert],
+) -> Result<(), String> {
+    let per_artist: Vec<_> = selected
+        .iter()
+        .map(|(r, hits, rejected)| {
+            let mut entry = serde_json::json!({
+                "artist": { "id": r.artist.id, "name": r.artist.name },
+                "total": r.infos.len(),
+                "hits": hits,
+            });
+            if explain {
+                entry["rejected"] = serde_json::json!(rejected);
+            }
+            entry
+        })
+        .collect();
+    let mut report = match per_artist.as_slice() {
+        [single] if !batch => single.clone(),
+        _ => serde_json::json!({ "artists": per_artist }),
+    };
+    report["query"] = query.into();
+    report["currency"] = currency.into();
+    if let Some(list) = shopping {
+        report["shopping_list"] = serde_json::json!({
+            "budget": list.budget,
+            "objective": list.objective.to_string(),
+            "total": list.total,
+            "value": list.value,
+            "items": list.items,
+        });
+    }
+    if !alerts.is_empty() {
+        report["alerts"] = serde_json::json!(alerts);
+    }
+    let text = serde_json::to_string_pretty(&report).map_err(|e| format!("JSON output: {e}"))?;
+    println!("{text}");
+    Ok(())
+}
+
+/// Format an amount in the given currency: "$12.50" for USD, "12.50 EUR" otherwise.
+fn money(amount: f64, currency: &str) -> String {
+    if currency == "USD" {
+        format!("${amount:.2}")
+    } else {
+        format!("{amount:.2} {currency}")
+    }
+}
+
 /// Format artist credits into a display string.
 /// Uses `anv` (artist name variation) when present, otherwise `name`.
 /// Joins with the `join` field from the API (e.g. "&", ",").
@@ -1850,3 +6518,524 @@ fn trunc(s: &str, max: usize) -> String {
         format!("{t}...")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inline format strings as /artists/{id}/releases returns them, and
+    /// the expected entries: (qty, name, descriptions, free text).
+    #[allow(clippy::type_complexity)]
+    const INLINE_FIXTURES: &[(&str, &[(u32, &str, &[&str], &[&str])])] = &[
+        ("", &[]),
+        ("CD, Album", &[(1, "CD", &["Album"], &[])]),
+        (
+            "Vinyl, 7\", 45 RPM, Single",
+            &[(1, "Vinyl", &["7\"", "45 RPM", "Single"], &[])],
+        ),
+        (
+            "2×Vinyl, LP, Album, Limited Edition, Clear",
+            &[(2, "Vinyl", &["LP", "Album", "Limited Edition"], &["Clear"])],
+        ),
+        (
+            "2xFile, FLAC, Album",
+            &[(2, "File", &["FLAC", "Album"], &[])],
+        ),
+        (
+            "Vinyl, LP, Album, RE, RM",
+            &[(1, "Vinyl", &["LP", "Album", "Reissue", "Remastered"], &[])],
+        ),
+        (
+            "Vinyl, 12\", 33 ⅓ RPM, EP, W/Lbl, TP",
+            &[(
+                1,
+                "Vinyl",
+                &["12\"", "33 ⅓ RPM", "EP", "White Label", "Test Pressing"],
+                &[],
+            )],
+        ),
+        (
+            "CD, Album, Enh + DVD, PAL",
+            &[
+                (1, "CD", &["Album", "Enhanced"], &[]),
+                (1, "DVD", &["PAL"], &[]),
+            ],
+        ),
+        (
+            "Box Set, Comp, 3×CD, Album, Remastered",
+            &[
+                (1, "Box Set", &["Compilation"], &[]),
+                (3, "CD", &["Album", "Remastered"], &[]),
+            ],
+        ),
+        (
+            "8-Track Cartridge, Album",
+            &[(1, "8-Track Cartridge", &["Album"], &[])],
+        ),
+        (
+            "Flexi-disc, 7\", S/Sided, Promo",
+            &[(1, "Flexi-disc", &["7\"", "Single Sided", "Promo"], &[])],
+        ),
+        (
+            "Lathe Cut, 10\", 78 RPM, Numbered, Hand-cut",
+            &[(
+                1,
+                "Lathe Cut",
+                &["10\"", "78 RPM", "Numbered"],
+                &["Hand-cut"],
+            )],
+        ),
+        (
+            "cassette, Album, Ltd, Red shell",
+            &[(1, "Cassette", &["Album", "Limited Edition"], &["Red shell"])],
+        ),
+        ("Album, Promo", &[(1, "", &["Album", "Promo"], &[])]),
+    ];
+
+    #[test]
+    fn parse_inline_fixtures() {
+        let registry = FormatRegistry::new(&[]);
+        for (input, expected) in INLINE_FIXTURES {
+            let expected: Vec<InlineFormat> = expected
+                .iter()
+                .map(|(qty, name, descriptions, free_text)| InlineFormat {
+                    qty: *qty,
+                    name: name.to_string(),
+                    descriptions: descriptions.iter().map(|d| d.to_string()).collect(),
+                    free_text: free_text.iter().map(|t| t.to_string()).collect(),
+                })
+                .collect();
+            assert_eq!(registry.parse_inline(input), expected, "parsing {input:?}");
+        }
+    }
+
+    #[test]
+    fn parse_inline_config_format() {
+        let extra = FormatDef {
+            name: "Hologram".into(),
+            aliases: Vec::new(),
+            search: false,
+        };
+        let registry = FormatRegistry::new(&[extra]);
+        let parsed = registry.parse_inline("2×Hologram, Single");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!((parsed[0].qty, parsed[0].name.as_str()), (2, "Hologram"));
+    }
+
+    #[test]
+    fn format_suggestions() {
+        assert_eq!(edit_distance("vinyl", "vinyl"), 0);
+        assert_eq!(edit_distance("vinly", "vinyl"), 1);
+        assert_eq!(edit_distance("ivnyl", "vinyl"), 1);
+        assert_eq!(edit_distance("vnly", "vinyl"), 2);
+        assert_eq!(edit_distance("", "cd"), 2);
+
+        let registry = FormatRegistry::new(&[]);
+        assert_eq!(registry.suggest("Vinly"), Some("Vinyl"));
+        assert_eq!(registry.suggest("casete"), Some("Cassette"));
+        // Aliases suggest their canonical name
+        assert_eq!(registry.suggest("flexy"), Some("Flexi-disc"));
+        // One edit allowed per three characters, and at least one
+        assert_eq!(registry.suggest("vnly"), None);
+        assert_eq!(registry.suggest("caste"), None);
+        assert_eq!(registry.suggest("xyz"), None);
+    }
+
+    #[test]
+    fn strip_profile_markup() {
+        assert_eq!(
+            strip_discogs_markup(
+                "Founded by [a=Miles Davis] on [l123] [b]and[/b]\n[url=https://x]site[/url] [live] [img=x.jpg]"
+            ),
+            "Founded by Miles Davis on and site [live]"
+        );
+    }
+
+    #[test]
+    fn artist_named_like_subcommand() {
+        // Flags go before or after the subcommand
+        for args in [
+            &["x", "why", "master", "5", "--only", "vinyl"][..],
+            &["x", "--only", "vinyl", "why", "master", "5"],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            assert!(matches!(cli.command, Some(Command::Why { id: 5, .. })));
+            assert_eq!(cli.only, ["vinyl"]);
+            cli.check_subcommand().unwrap();
+        }
+        let cli = Cli::try_parse_from(["x", "--only", "vinyl", "prices", "history", "5"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Prices { .. })));
+        assert!(Cli::try_parse_from(["x", "why"]).is_err());
+        let cli = Cli::try_parse_from(["x", "Artist", "why", "master", "5"]).unwrap();
+        assert!(cli.check_subcommand().is_err());
+
+        for args in [
+            &["x", "--", "why"][..],
+            &["x", "--only", "vinyl", "--", "prices"],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            assert!(cli.command.is_none(), "{args:?}");
+            assert_eq!(cli.artist.as_deref(), args.last().copied());
+        }
+    }
+
+    #[test]
+    fn role_filter() {
+        let written = Role::parse("Written-By");
+        assert_eq!(written, Role::parse("written by"));
+        assert_eq!(written.to_string(), "Written-By");
+
+        let filter = |args: &[&str]| {
+            let cli = Cli::try_parse_from(args).unwrap();
+            Filters::from_cli(&cli, FormatRegistry::new(&[])).map(|f| f.roles)
+        };
+        let roles = |names: &[&str]| names.iter().map(|n| Role::parse(n)).collect();
+        let want = filter(&["x", "a", "--role", "written-by,remix"]).unwrap();
+        assert_eq!(want.rejection(&roles(&["Written-By"])), None);
+        assert_eq!(
+            want.rejection(&roles(&["Main"])).as_deref(),
+            Some("credited as Main (--role Remix, written-by)")
+        );
+        let not = filter(&["x", "a", "--not-role", "appearance"]).unwrap();
+        assert!(not.rejection(&roles(&["Appearance"])).is_some());
+        assert_eq!(not.rejection(&roles(&["Appearance", "Main"])), None);
+        assert!(filter(&["x", "a", "--main-only", "--role", "remix"]).is_err());
+    }
+
+    #[test]
+    fn list_filters_apply_to_why() {
+        let rejection = |args: &[&str], kind, title| {
+            let cli = Cli::try_parse_from(args).unwrap();
+            let filters = Filters::from_cli(&cli, FormatRegistry::new(&[])).unwrap();
+            filters
+                .list
+                .rejection(kind, title, Some(1959), &mut ApiStats::default())
+        };
+        // List flags go before or after `why`
+        let args = ["x", "why", "release", "5", "--year-from", "1970"];
+        assert_eq!(
+            rejection(&args, "release", "Kind of Blue").as_deref(),
+            Some("1959 (--year-from)")
+        );
+        let args = [
+            "x",
+            "--masters-only",
+            "--title-exclude",
+            "blue",
+            "why",
+            "release",
+            "5",
+        ];
+        assert_eq!(
+            rejection(&args, "release", "Kind of Blue").as_deref(),
+            Some("release (--masters-only)")
+        );
+        assert!(rejection(&args, "master", "Kind of Blue").is_some());
+
+        let cli = Cli::try_parse_from(["x", "a", "--masters-only", "--releases-only"]).unwrap();
+        let err = Filters::from_cli(&cli, FormatRegistry::new(&[])).err();
+        assert_eq!(
+            err.as_deref(),
+            Some("--masters-only and --releases-only conflict")
+        );
+    }
+
+    #[test]
+    fn why_checks_artist_listing() {
+        let cli = Cli::try_parse_from([
+            "x",
+            "why",
+            "release",
+            "5",
+            "--only",
+            "vinyl",
+            "--main-only",
+            "--exclude-owned",
+        ])
+        .unwrap();
+        assert!(cli.exclude_owned);
+        let filters = Filters::from_cli(&cli, FormatRegistry::new(&[])).unwrap();
+        let listed = ArtistListing {
+            artist: "Artist".into(),
+            kind: "release",
+            entry: Some(DedupRelease {
+                id: 5,
+                kind: "release".into(),
+                title: "Title".into(),
+                year: None,
+                roles: BTreeSet::from([Role::Appearance]),
+                format: Some("CD, Album".into()),
+                credited: vec!["Artist".into()],
+            }),
+        };
+        let role = listed.role_verdict(&filters);
+        assert_eq!((role.stage, role.pass), (Stage::RoleFilter, false));
+        assert_eq!(role.detail, "credited as Appearance (--role Main)");
+        let inline = listed.inline_verdict(&filters);
+        assert_eq!((inline.stage, inline.pass), (Stage::InlinePrefilter, false));
+        assert_eq!(inline.detail, "cd (outside --only)");
+
+        let unlisted = ArtistListing {
+            entry: None,
+            ..listed
+        };
+        assert!(!unlisted.role_verdict(&filters).pass);
+        assert!(unlisted.inline_verdict(&filters).pass);
+    }
+
+    #[test]
+    fn why_matches_run_for_flagged_versions() {
+        // An official vinyl LP and a bootleg CD of the same master
+        let page = serde_json::json!({
+            "pagination": {"page": 1, "pages": 1, "items": 2},
+            "versions": [
+                {"id": 1, "major_formats": ["Vinyl"], "format": "LP, Album", "country": "UK"},
+                {"id": 2, "major_formats": ["CD"], "format": "Album, Unofficial Release"},
+            ],
+        });
+        let scan = |policy: &str| {
+            let cli = Cli::try_parse_from([
+                "x",
+                "artist",
+                "--only",
+                "vinyl",
+                "--official-only",
+                "--flagged-versions",
+                policy,
+            ])
+            .unwrap();
+            let filters = Filters::from_cli(&cli, FormatRegistry::new(&[])).unwrap();
+            let scan = scan_versions(&filters, true, |_| {
+                serde_json::from_value(page.clone()).map_err(|e| e.to_string())
+            })
+            .unwrap();
+            // What `why` shows first failing, and what the run drops on
+            let shown = scan
+                .verdicts(&filters)
+                .into_iter()
+                .find(|v| !v.pass)
+                .map(|v| (v.stage, v.detail));
+            assert_eq!(shown, scan.rejection(&filters), "{policy}");
+            shown
+        };
+
+        let (stage, why) = scan("count").expect("bootleg CD counts against --only");
+        assert_eq!(stage, Stage::EarlyExit);
+        assert_eq!(why, "cd (outside --only)");
+        assert_eq!(scan("ignore"), None);
+    }
+
+    #[test]
+    fn alert_fires_on_crossing_and_drop() {
+        let snap = |price: f64| PriceSnapshot {
+            kind: "release".into(),
+            id: 1,
+            ts: 1790000000,
+            currency: "USD".into(),
+            lowest_price: Some(price),
+            num_for_sale: Some(2),
+        };
+        // Crossing the threshold fires, staying below it doesn't
+        assert_eq!(
+            alert_reasons(19.0, Some(&snap(22.0)), Some(20.0), None, "USD"),
+            ["below $20.00"]
+        );
+        assert!(alert_reasons(18.0, Some(&snap(19.0)), Some(20.0), None, "USD").is_empty());
+        // A 25% drop against a 20% threshold
+        assert_eq!(
+            alert_reasons(30.0, Some(&snap(40.0)), None, Some(20.0), "USD"),
+            ["down 25% since 2026-09-21"]
+        );
+        assert!(alert_reasons(35.0, Some(&snap(40.0)), None, Some(20.0), "USD").is_empty());
+        // No previous observation: only the threshold can fire
+        assert_eq!(
+            alert_reasons(5.0, None, Some(20.0), Some(20.0), "USD"),
+            ["below $20.00"]
+        );
+    }
+
+    /// A hit on release `id` whose price was fetched but isn't shown, as
+    /// on a run without price flags.
+    fn hit(id: u64, price: f64) -> Info {
+        Info {
+            kind: "release",
+            id,
+            title: "Title".into(),
+            year: None,
+            roles: BTreeSet::new(),
+            credited: Vec::new(),
+            formats: BTreeSet::from(["Vinyl".to_string()]),
+            descriptions: None,
+            countries: BTreeSet::new(),
+            label: None,
+            catno: None,
+            genres: Vec::new(),
+            styles: Vec::new(),
+            community: None,
+            url: format!("https://www.discogs.com/release/{id}"),
+            lowest_price: None,
+            num_for_sale: None,
+            market: Market {
+                lowest_price: Some(price),
+                num_for_sale: Some(3),
+            },
+            artists: Vec::new(),
+            release_id: Some(id),
+            version_ids: Vec::new(),
+            rejection: None,
+            last_seen: None,
+        }
+    }
+
+    fn report() -> ArtistReport {
+        ArtistReport {
+            artist: serde_json::from_value(serde_json::json!({"id": 1, "name": "Artist"})).unwrap(),
+            infos: Vec::new(),
+            rejected: Vec::new(),
+            total: 1,
+            dedup_saved: 0,
+        }
+    }
+
+    #[test]
+    fn budget_plan() {
+        let priced = |id, price| Info {
+            lowest_price: Some(price),
+            num_for_sale: Some(1),
+            ..hit(id, price)
+        };
+        let report = report();
+        let plan = |hits: &[Vec<&Info>], budget| {
+            let selected: Vec<Selection> = hits
+                .iter()
+                .map(|h| (&report, h.clone(), Vec::new()))
+                .collect();
+            let list = plan_budget(&selected, budget, Objective::Count, &HashMap::new());
+            assert!(list.total <= budget + 1e-9, "{} over {budget}", list.total);
+            let ids: Vec<u64> = list.items.iter().map(|r| r.id).collect();
+            (ids, list.value)
+        };
+        let (a, b, c) = (priced(1, 3.34), priced(2, 3.33), priced(3, 3.33));
+        // Exactly the budget to the cent
+        assert_eq!(plan(&[vec![&a, &b, &c]], 10.0), (vec![1, 2, 3], 3));
+        // A cent short: two fit, and the cheaper pair is picked
+        let d = priced(4, 3.35);
+        assert_eq!(plan(&[vec![&d, &a, &b]], 10.0), (vec![1, 2], 2));
+        // Equal value: the smaller spend wins
+        let (e, f) = (priced(5, 5.01), priced(6, 5.0));
+        assert_eq!(plan(&[vec![&e, &f]], 10.0), (vec![6], 1));
+        // A hit found under two artists is bought once
+        assert_eq!(plan(&[vec![&e], vec![&e, &f]], 100.0), (vec![5, 6], 2));
+    }
+
+    #[test]
+    fn price_history_records_unshown_prices() {
+        let dir = std::env::temp_dir().join(format!("dff-prices-{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache_dir = dir.to_string_lossy().into_owned();
+        let mut history = PriceHistory::load(&cache_dir);
+        // An older observation, then this run's (once per item)
+        history.entries.push(PriceSnapshot {
+            kind: "release".into(),
+            id: 7,
+            ts: 1700000000,
+            currency: "USD".into(),
+            lowest_price: Some(40.0),
+            num_for_sale: Some(1),
+        });
+        history.record([&hit(7, 30.0), &hit(7, 30.0)], "USD");
+        assert_eq!(PriceHistory::load(&cache_dir).entries.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let last = history.last_seen(&hit(7, 24.0), "USD").unwrap();
+        assert_eq!(last.price(), Some(30.0));
+        assert!(history.last_seen(&hit(7, 24.0), "EUR").is_none());
+        assert!(
+            last.change_to(Some(24.0), "USD")
+                .starts_with("▼ -$6.00 since ")
+        );
+        assert!(last.change_to(Some(30.0), "USD").starts_with("= since "));
+        assert!(last.change_to(None, "USD").starts_with("was $30.00 on "));
+
+        // --alert-drop compares with the last observation, not the oldest
+        let cli = Cli::try_parse_from(["x", "artist", "--alert-drop", "20"]).unwrap();
+        let report = report();
+        let current = hit(7, 24.0);
+        let selected = [(&report, vec![&current], Vec::new())];
+        let alerts = find_alerts(&selected, &history, &HashMap::new(), &cli, "USD");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].previous_price, Some(30.0));
+        assert!(alerts[0].reasons[0].starts_with("down 20% since "));
+    }
+
+    #[test]
+    fn alert_flags_alone_find_alerts() {
+        let cli = Cli::try_parse_from(["x", "artist", "--alert-below", "20"]).unwrap();
+
+        let hit = hit(7, 15.0);
+        let report = report();
+        let history = PriceHistory {
+            path: String::new(),
+            entries: Vec::new(),
+        };
+        let selected = [(&report, vec![&hit], Vec::new())];
+        let alerts = find_alerts(&selected, &history, &HashMap::new(), &cli, "USD");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!((alerts[0].id, alerts[0].price), (7, 15.0));
+        assert_eq!(alerts[0].reasons, ["below $20.00"]);
+    }
+
+    #[test]
+    fn webhook_receives_alert() {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        // A one-shot local stand-in for the webhook receiver
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let receiver = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut reader = io::BufReader::new(conn.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    len = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            conn.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+            (request_line, String::from_utf8(body).unwrap())
+        });
+
+        let alert = PriceAlert {
+            kind: "master".into(),
+            id: 5,
+            title: "Title".into(),
+            url: "https://www.discogs.com/master/5".into(),
+            currency: "USD".into(),
+            price: 9.5,
+            previous_price: Some(14.0),
+            since: Some("2026-09-28".into()),
+            reasons: vec!["down 32% since 2026-09-28".into()],
+            date: "2026-10-05".into(),
+        };
+        post_webhook(&url, &alert).unwrap();
+
+        let (request_line, body) = receiver.join().unwrap();
+        assert!(request_line.starts_with("POST /hook "), "{request_line}");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["id"], 5);
+        assert_eq!(body["price"], 9.5);
+        assert_eq!(body["reasons"][0], "down 32% since 2026-09-28");
+    }
+}